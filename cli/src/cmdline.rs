@@ -19,8 +19,10 @@ pub struct Args {
 pub enum Cmd {
     /// Create or update index
     Index {
-        /// Source root directory
-        #[arg(short, long, value_hint = clap::ValueHint::DirPath, default_value = CurrentDir)]
+        /// Source root directory (or tar archive like `linux-6.6.tar.xz`)
+        ///
+        /// Index of archive is stored into subdirectory named by hash of archive contents.
+        #[arg(short, long, value_hint = clap::ValueHint::AnyPath, default_value = CurrentDir)]
         source: PathBuf,
 
         /// Data coding
@@ -30,6 +32,46 @@ pub enum Cmd {
         /// Data compression
         #[arg(short = 'z', long, env = "KQUERY_COMPRESS", value_enum, default_value_t = DataCompress::default())]
         compress: DataCompress,
+
+        /// Rebuild index from scratch instead of updating existing index (merged external modules is kept)
+        #[arg(long)]
+        full: bool,
+
+        /// Update existing index reusing data of unchanged files (fails when there is no index)
+        #[arg(long, conflicts_with = "full")]
+        incremental: bool,
+
+        #[cfg(feature = "git")]
+        /// Index git revision (commit, tag or branch) of repository without checkout
+        /// (index is stored into subdirectory named by tree hash)
+        #[arg(short, long)]
+        rev: Option<String>,
+    },
+
+    /// Index external (out-of-tree) modules directory and merge it into existing index
+    External {
+        /// External modules directory (with Kbuild or Makefile)
+        #[arg(value_parser, value_hint = clap::ValueHint::DirPath, name = "path/to/modules")]
+        dir: PathBuf,
+
+        /// Path prefix for merged sources (`extra/<dir-name>` by default)
+        #[arg(short, long)]
+        prefix: Option<PathBuf>,
+
+        /// Kernel source root directory (used to resolve includes like `make -C <kernel> M=dir`)
+        #[arg(short, long, value_hint = clap::ValueHint::DirPath, default_value = CurrentDir)]
+        source: PathBuf,
+    },
+
+    /// Show differences between two indexes (like for two kernel versions)
+    Diff {
+        /// Old index file or directory
+        #[arg(value_parser, value_hint = clap::ValueHint::AnyPath, name = "old-index")]
+        old: PathBuf,
+
+        /// New index file or directory
+        #[arg(value_parser, value_hint = clap::ValueHint::AnyPath, name = "new-index")]
+        new: PathBuf,
     },
 
     /// List of processed sources
@@ -38,6 +80,11 @@ pub enum Cmd {
         /// Optional pattern to filter paths
         #[arg(name = "glob-pattern")]
         pattern: Option<String>,
+
+        /// List only sources which will be built using kernel configuration
+        /// (.config, auto.conf or build directory)
+        #[arg(short, long, value_hint = clap::ValueHint::AnyPath)]
+        config: Option<PathBuf>,
     },
 
     /// List of known compatible string
@@ -56,6 +103,14 @@ pub enum Cmd {
         pattern: Option<String>,
     },
 
+    /// List of module parameters with types, permissions and descriptions
+    Params {
+        #[cfg(feature = "glob")]
+        /// Optional pattern to filter parameters (like `arm_smmu.*`)
+        #[arg(value_parser)]
+        pattern: Option<String>,
+    },
+
     /// Query source info by compatible string
     Compat {
         /// Compatible string
@@ -68,6 +123,97 @@ pub enum Cmd {
         /// Configuration option
         #[arg(value_parser, name = "CONFIG_OPTION")]
         config: String,
+
+        /// Show dependency chain
+        #[arg(short, long)]
+        deps: bool,
+    },
+
+    /// Query source info by ACPI identifier
+    Acpi {
+        /// ACPI or PNP identifier
+        #[arg(value_parser, name = "ACPI_ID")]
+        id: String,
+    },
+
+    /// Query source info by PCI identifier
+    Pci {
+        /// PCI vendor and device identifiers
+        #[arg(value_parser, name = "vendor:device")]
+        id: String,
+    },
+
+    /// Query source info by USB identifier
+    Usb {
+        /// USB vendor and product identifiers
+        #[arg(value_parser, name = "vendor:product")]
+        id: String,
+    },
+
+    /// Query source info by I2C device name
+    I2c {
+        /// I2C device name
+        #[arg(value_parser, name = "device-name")]
+        id: String,
+    },
+
+    /// Query source info by SPI device name
+    Spi {
+        /// SPI device name
+        #[arg(value_parser, name = "device-name")]
+        id: String,
+    },
+
+    /// Query source info by platform device name
+    Platform {
+        /// Platform device name
+        #[arg(value_parser, name = "device-name")]
+        id: String,
+    },
+
+    /// Query drivers by modalias
+    Modalias {
+        /// Modalias string (reads modaliases line by line from stdin when omitted)
+        #[arg(value_parser, name = "modalias")]
+        modalias: Option<String>,
+    },
+
+    /// Query drivers for compatible strings used by devicetree source
+    Dts {
+        /// Devicetree source path
+        #[arg(value_parser, name = "path/to/board.dts")]
+        path: PathBuf,
+    },
+
+    /// Report compatible strings without bindings and bindings without drivers
+    Bindings,
+
+    /// Query maintainers by source path, compatible string or configuration option
+    Maintainers {
+        /// Source path, compatible string or configuration option
+        #[arg(value_parser, name = "path|compat|CONFIG_OPTION")]
+        query: String,
+    },
+
+    /// Query sources which exports symbol
+    Symbol {
+        /// Symbol name
+        #[arg(value_parser, name = "symbol")]
+        symbol: String,
+    },
+
+    /// Query modules and configuration options which source depends on through used symbols
+    Deps {
+        /// Source path or module name
+        #[arg(value_parser, name = "path/to/source.c|module")]
+        source: String,
+    },
+
+    /// Query kernel module by name, path or member source
+    Module {
+        /// Module name, module path or source path
+        #[arg(value_parser, name = "module|path/to/source.c")]
+        module: String,
     },
 
     /// Query source info by path
@@ -76,6 +222,24 @@ pub enum Cmd {
         #[arg(value_parser, name = "path/to/source.c")]
         source: PathBuf,
     },
+
+    /// Query files included by source or header
+    Includes {
+        /// Source or header path
+        #[arg(value_parser, name = "path/to/source.c")]
+        source: PathBuf,
+    },
+
+    /// Query sources and headers which includes header
+    IncludedBy {
+        /// Header path or include name
+        #[arg(value_parser, name = "linux/header.h")]
+        header: PathBuf,
+
+        /// Show indirect includes too
+        #[arg(short, long)]
+        recursive: bool,
+    },
 }
 
 struct CurrentDir;
@@ -1,7 +1,10 @@
 mod cmdline;
 
 use cmdline::{Args, Cmd};
-use kquery::{DataOptions, FileMgr, MetaData, Result, SourceData};
+use kquery::{
+    BindingData, DataOptions, DeviceBus, DotConfig, FileMgr, KmodData, MetaData, ModuleData,
+    Result, SourceData,
+};
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -16,17 +19,93 @@ async fn main() -> Result<()> {
             source,
             coding,
             compress,
-            ..
+            full,
+            incremental,
+            #[cfg(feature = "git")]
+            rev,
         } => {
             let opts = DataOptions::new(coding, compress);
 
-            let filemgr = FileMgr::new(source).await?;
+            #[cfg(feature = "git")]
+            let filemgr = if let Some(rev) = rev {
+                Some(FileMgr::from_git(source, rev).await?)
+            } else {
+                None
+            };
+            #[cfg(not(feature = "git"))]
+            let filemgr = None;
+
+            #[cfg(feature = "archive")]
+            let filemgr = if filemgr.is_none() && kquery::is_archive(source) {
+                Some(FileMgr::from_archive(source).await?)
+            } else {
+                filemgr
+            };
+
+            let filemgr = if let Some(filemgr) = filemgr {
+                filemgr
+            } else {
+                FileMgr::new(source).await?
+            };
+
+            // indexes of git revisions is cached by tree hash
+            let data_path = if let Some(tree_id) = filemgr.tree_id() {
+                let data_path = args.data_path.join(tree_id);
+                tokio::fs::create_dir_all(&data_path).await?;
+                data_path
+            } else {
+                args.data_path.clone()
+            };
+
+            let existing = MetaData::from_path(&data_path, None)
+                .await
+                .unwrap_or_else(|error| {
+                    log::warn!("Unable to load existing index due to: {}", error);
+                    None
+                });
 
-            println!("Creating index for {:?}...", filemgr.base_path());
+            // external modules of working tree should be merged again (even on full rebuild)
+            let externals = existing
+                .as_ref()
+                .filter(|_| filemgr.tree_id().is_none())
+                .map(|existing| existing.externals.clone())
+                .unwrap_or_default();
 
-            let db = MetaData::from_kbuild(&filemgr).await?;
+            if *incremental && existing.is_none() {
+                return Err(kquery::Error::msg(format!(
+                    "Index {:?} not found!",
+                    data_path
+                )));
+            }
+
+            // update existing index when possible
+            let previous = if *full { None } else { existing };
+
+            if previous.is_some() && filemgr.tree_id().is_some() {
+                println!("Index already exists at {:?}", data_path);
+                return Ok(());
+            }
+
+            if previous.is_some() {
+                println!("Updating index for {:?}...", filemgr.base_path());
+            } else {
+                println!("Creating index for {:?}...", filemgr.base_path());
+            }
 
-            db.to_path(&args.data_path, &opts).await?;
+            let mut db = MetaData::from_kbuild_incremental(&filemgr, previous).await?;
+
+            for (prefix, dir) in externals {
+                match FileMgr::new(&dir).await {
+                    Ok(external) => db.index_external(&filemgr, &external, &prefix).await?,
+                    Err(error) => log::warn!(
+                        "Unable to index external modules {:?} due to: {}",
+                        dir,
+                        error
+                    ),
+                }
+            }
+
+            db.to_path(&data_path, &opts).await?;
 
             #[cfg(feature = "alert-orphan-sources")]
             {
@@ -63,9 +142,147 @@ async fn main() -> Result<()> {
             );
         }
 
+        Cmd::External {
+            dir,
+            prefix,
+            source,
+        } => {
+            let path = if args.data_path.is_dir() {
+                MetaData::find_file(&args.data_path).await?
+            } else {
+                Some(args.data_path.clone())
+            };
+
+            let db = if let Some(path) = path {
+                MetaData::from_file(&path, None).await?.map(|db| (path, db))
+            } else {
+                None
+            };
+
+            if let Some((path, mut db)) = db {
+                let kernel = FileMgr::new(source).await?;
+                let filemgr = FileMgr::new(dir).await?;
+
+                // like `make M=dir modules_install` does by default
+                let prefix = if let Some(prefix) = prefix {
+                    prefix.clone()
+                } else {
+                    std::path::Path::new("extra")
+                        .join(std::fs::canonicalize(dir)?.file_name().unwrap_or_default())
+                };
+
+                println!(
+                    "Indexing external modules {:?} as {:?}...",
+                    filemgr.base_path(),
+                    prefix
+                );
+
+                db.index_external(&kernel, &filemgr, &prefix).await?;
+
+                db.to_file(&path, None).await?;
+
+                println!(
+                    "Found {} external sources",
+                    db.sources
+                        .keys()
+                        .filter(|source| source.starts_with(&prefix))
+                        .count()
+                );
+            } else {
+                eprintln!("Index does not exists!");
+                eprintln!("Please run `kquery index` first...");
+            }
+        }
+
+        Cmd::Diff { old, new } => {
+            let load = |path| async move {
+                MetaData::from_path(path, None)
+                    .await?
+                    .ok_or_else(|| kquery::Error::msg(format!("Index {:?} not found!", path)))
+            };
+            let old = load(old).await?;
+            let new = load(new).await?;
+
+            let diff = old.diff(&new);
+
+            if diff.is_empty() {
+                println!("No differences found");
+            }
+
+            for (title, entries) in [
+                ("Added sources", &diff.added_sources),
+                ("Removed sources", &diff.removed_sources),
+            ] {
+                if !entries.is_empty() {
+                    println!("{}:", title);
+                    for entry in entries {
+                        println!("    {}", entry.display());
+                    }
+                }
+            }
+            if !diff.moved_sources.is_empty() {
+                println!("Moved sources:");
+                for (old, new) in &diff.moved_sources {
+                    println!("    {} => {}", old.display(), new.display());
+                }
+            }
+            for (title, entries) in [
+                ("Added compatible strings", &diff.added_compat_strs),
+                ("Removed compatible strings", &diff.removed_compat_strs),
+                ("Added module parameters", &diff.added_params),
+                ("Removed module parameters", &diff.removed_params),
+            ] {
+                if !entries.is_empty() {
+                    println!("{}:", title);
+                    for entry in entries {
+                        println!("    {}", entry);
+                    }
+                }
+            }
+            if !diff.retyped_params.is_empty() {
+                println!("Module parameters with changed type:");
+                for (name, old_type, new_type) in &diff.retyped_params {
+                    println!("    {}: {} => {}", name, old_type, new_type);
+                }
+            }
+            if !diff.config_opts.is_empty() {
+                println!("Configuration options with changed sources:");
+                for config_opt in &diff.config_opts {
+                    println!("    {}", config_opt.name);
+                    for source in &config_opt.removed_sources {
+                        println!("        - {}", source.display());
+                    }
+                    for source in &config_opt.added_sources {
+                        println!("        + {}", source.display());
+                    }
+                }
+            }
+            if !diff.licenses.is_empty() {
+                println!("License changes:");
+                for (source, old_license, new_license) in &diff.licenses {
+                    println!(
+                        "    {}: {} => {}",
+                        source.display(),
+                        old_license,
+                        new_license
+                    );
+                }
+            }
+        }
+
         cmd => {
             if let Some(db) = MetaData::from_path(&args.data_path, None).await? {
-                fn print_source_data(ident: &str, source_data: &SourceData) {
+                fn print_source_data(
+                    ident: &str,
+                    source_data: &SourceData,
+                    module: Option<ModuleData>,
+                ) {
+                    if !source_data.lang.is_c() {
+                        println!("{}Language: {}", ident, source_data.lang);
+                    }
+                    if !source_data.condition.is_true() {
+                        println!("{}Build condition: {}", ident, source_data.condition);
+                    }
                     if !source_data.config_opts.is_empty() {
                         println!("{}Configuration options:", ident);
                         for condition in &source_data.config_opts {
@@ -78,10 +295,108 @@ async fn main() -> Result<()> {
                             println!("{}    {}", ident, compat);
                         }
                     }
+                    if let Some(module) = &module {
+                        if !module.is_empty() {
+                            println!("{}Module:", ident);
+                            if !module.name.is_empty() {
+                                println!("{}    Name: {}", ident, module.name);
+                            }
+                            print_module_data(&format!("{}    ", ident), module);
+                        }
+                    }
+                    for (name, table) in &source_data.device_tables {
+                        println!(
+                            "{}Device table: {} ({}{})",
+                            ident,
+                            name,
+                            table.bus,
+                            if table.exported { ", exported" } else { "" }
+                        );
+                        for entry in &table.entries {
+                            if entry.data.is_empty() {
+                                println!("{}    {}", ident, entry.id);
+                            } else {
+                                println!("{}    {} => {}", ident, entry.id, entry.data);
+                            }
+                        }
+                    }
+                }
+
+                fn print_binding_data(ident: &str, binding_data: &BindingData) {
+                    if !binding_data.title.is_empty() {
+                        println!("{}Title: {}", ident, binding_data.title);
+                    }
+                    if !binding_data.id.is_empty() {
+                        println!("{}Schema: {}", ident, binding_data.id);
+                    }
+                    if !binding_data.maintainers.is_empty() {
+                        println!("{}Maintainers:", ident);
+                        for maintainer in &binding_data.maintainers {
+                            println!("{}    {}", ident, maintainer);
+                        }
+                    }
+                }
+
+                fn print_module_data(ident: &str, module: &ModuleData) {
+                    if !module.description.is_empty() {
+                        println!("{}Description: {}", ident, module.description);
+                    }
+                    if !module.license.is_empty() {
+                        println!("{}License: {}", ident, module.license);
+                    }
+                    if !module.authors.is_empty() {
+                        println!("{}Authors:", ident);
+                        for author in &module.authors {
+                            println!("{}    {}", ident, author);
+                        }
+                    }
+                    if !module.aliases.is_empty() {
+                        println!("{}Aliases:", ident);
+                        for alias in &module.aliases {
+                            println!("{}    {}", ident, alias);
+                        }
+                    }
+                    if !module.imports.is_empty() {
+                        println!("{}Imported namespaces:", ident);
+                        for import in &module.imports {
+                            println!("{}    {}", ident, import);
+                        }
+                    }
+                    if !module.params.is_empty() {
+                        println!("{}Parameters:", ident);
+                        for (name, param) in &module.params {
+                            println!(
+                                "{}    {}: {} ({:04o})",
+                                ident, name, param.type_, param.perm
+                            );
+                            if !param.description.is_empty() {
+                                println!("{}        {}", ident, param.description);
+                            }
+                            if let Some(source) = &param.source {
+                                println!("{}        Defined in: {}", ident, source.display());
+                            }
+                        }
+                    }
+                }
+
+                fn print_kmod_data(ident: &str, kmod: &std::path::Path, kmod_data: &KmodData) {
+                    println!("{}Path: {}", ident, kmod.display());
+                    if !kmod_data.condition.is_true() {
+                        println!("{}Build condition: {}", ident, kmod_data.condition);
+                    }
+                    match kmod_data.builtin {
+                        Some(true) => println!("{}Built-in", ident),
+                        Some(false) => println!("{}Loadable", ident),
+                        None => println!("{}Built-in or loadable (by configuration)", ident),
+                    }
+                    println!("{}Sources:", ident);
+                    for source in &kmod_data.sources {
+                        println!("{}    {}", ident, source.display());
+                    }
                 }
 
-                fn print_entries_list<P: AsRef<std::path::Path>>(
-                    entries: impl Iterator<Item = P>,
+                fn print_entries_list<P: AsRef<std::path::Path>, S: std::fmt::Display>(
+                    entries: impl Iterator<Item = (P, S)>,
                     #[cfg(feature = "glob")] pattern: Option<impl AsRef<str>>,
                 ) -> Result<()> {
                     #[cfg(feature = "glob")]
@@ -93,73 +408,206 @@ async fn main() -> Result<()> {
 
                     #[cfg(feature = "glob")]
                     let entries = if let Some(pattern) = &pattern {
-                        either::Either::Left(entries.filter(|path| pattern.is_match(path)))
+                        either::Either::Left(entries.filter(|(path, _)| pattern.is_match(path)))
                     } else {
                         either::Either::Right(entries)
                     };
 
-                    for entry in entries {
-                        println!("{}", entry.as_ref().display());
+                    for (entry, suffix) in entries {
+                        println!("{}{}", entry.as_ref().display(), suffix);
                     }
 
                     Ok(())
                 }
 
                 match cmd {
-                    Cmd::Index { .. } => unreachable!(),
+                    Cmd::Index { .. } | Cmd::External { .. } | Cmd::Diff { .. } => unreachable!(),
 
                     Cmd::Sources {
                         #[cfg(feature = "glob")]
                         pattern,
+                        config,
+                    } => {
+                        if let Some(config) = config {
+                            let config = DotConfig::from_path(config).await?;
+
+                            print_entries_list(
+                                db.built_sources(&config)
+                                    .map(|(source, tristate)| (source, format!(" [{}]", tristate))),
+                                #[cfg(feature = "glob")]
+                                pattern.as_ref(),
+                            )?;
+                        } else {
+                            print_entries_list(
+                                db.sources.keys().map(|source| (source, "")),
+                                #[cfg(feature = "glob")]
+                                pattern.as_ref(),
+                            )?;
+                        }
+                    }
+
+                    Cmd::Compats {
+                        #[cfg(feature = "glob")]
+                        pattern,
                     } => {
                         print_entries_list(
-                            db.sources.keys(),
+                            db.compat_strs.keys().map(|compat| (compat, "")),
                             #[cfg(feature = "glob")]
                             pattern.as_ref(),
                         )?;
                     }
 
-                    Cmd::Compats {
+                    Cmd::Configs {
                         #[cfg(feature = "glob")]
                         pattern,
                     } => {
                         print_entries_list(
-                            db.compat_strs.keys(),
+                            db.config_opts.keys().map(|config| (config, "")),
                             #[cfg(feature = "glob")]
                             pattern.as_ref(),
                         )?;
                     }
 
-                    Cmd::Configs {
+                    Cmd::Params {
                         #[cfg(feature = "glob")]
                         pattern,
                     } => {
                         print_entries_list(
-                            db.config_opts.keys(),
+                            db.module_params()
+                                .into_iter()
+                                .map(|(name, (source, param))| {
+                                    let mut suffix =
+                                        format!(": {} ({:04o})", param.type_, param.perm);
+                                    if !param.description.is_empty() {
+                                        suffix.push_str(" - ");
+                                        suffix.push_str(&param.description);
+                                    }
+                                    suffix.push_str(&format!(" [{}]", source.display()));
+                                    (name, suffix)
+                                }),
                             #[cfg(feature = "glob")]
                             pattern.as_ref(),
                         )?;
                     }
 
                     Cmd::Compat { compat } => {
-                        if let Some(compat_data) = db.compat_str(&compat) {
-                            println!("Source: {}", compat_data.source.display());
-                            if let Some(source_data) = db.source(&compat_data.source) {
-                                print_source_data("", source_data);
+                        if let Some(compat_data) = db.compat_str(compat) {
+                            if !compat_data.sources.is_empty() {
+                                println!("Sources:");
+                                for source in &compat_data.sources {
+                                    println!("    {}", source.display());
+                                    if let Some(source_data) = db.source(source) {
+                                        print_source_data(
+                                            "        ",
+                                            source_data,
+                                            db.source_module(source),
+                                        );
+                                    }
+                                }
+                            }
+                            if !compat_data.bindings.is_empty() {
+                                println!("Bindings:");
+                                for binding in &compat_data.bindings {
+                                    println!("    {}", binding.display());
+                                    if let Some(binding_data) = db.binding(binding) {
+                                        print_binding_data("        ", binding_data);
+                                    }
+                                }
                             }
                         } else {
                             eprintln!("Compatible string \"{}\" not found!", compat);
                         }
                     }
 
-                    Cmd::Config { config } => {
-                        if let Some(config_data) = db.config_opt(&config) {
+                    Cmd::Config { config, deps } => {
+                        if let Some(config_data) = db.config_opt(config) {
+                            if let Some(type_) = &config_data.type_ {
+                                println!("Type: {}", type_);
+                            }
+                            if !config_data.prompt.is_empty() {
+                                println!("Prompt: {}", config_data.prompt);
+                            }
+                            if !config_data.locations.is_empty() {
+                                println!("Defined at:");
+                                for location in &config_data.locations {
+                                    println!("    {}", location);
+                                }
+                            }
+                            if !config_data.help.is_empty() {
+                                println!("Help:");
+                                for line in config_data.help.lines() {
+                                    println!("    {}", line);
+                                }
+                            }
+                            if !config_data.depends_on.is_true() {
+                                println!("Depends on: {}", config_data.depends_on);
+                            }
+                            for (title, config_opts) in [
+                                ("Selects", &config_data.selects),
+                                ("Implies", &config_data.implies),
+                            ] {
+                                if !config_opts.is_empty() {
+                                    println!("{}:", title);
+                                    for (config_opt, condition) in config_opts {
+                                        if condition.is_true() {
+                                            println!("    {}", config_opt);
+                                        } else {
+                                            println!("    {} if {}", config_opt, condition);
+                                        }
+                                    }
+                                }
+                            }
+                            if !config_data.selected_by.is_empty() {
+                                println!("Selected by:");
+                                for config_opt in &config_data.selected_by {
+                                    println!("    {}", config_opt);
+                                }
+                            }
+                            if *deps {
+                                fn print_deps_chain(
+                                    db: &MetaData,
+                                    ident: &str,
+                                    config: &str,
+                                    visited: &mut std::collections::BTreeSet<String>,
+                                ) {
+                                    if let Some(config_data) = db.config_opt(config) {
+                                        for dep in config_data.depends_on.required_symbols() {
+                                            if visited.insert(dep.into()) {
+                                                println!("{}{}", ident, dep);
+                                                print_deps_chain(
+                                                    db,
+                                                    &format!("{}    ", ident),
+                                                    dep,
+                                                    visited,
+                                                );
+                                            } else {
+                                                println!("{}{} (see above)", ident, dep);
+                                            }
+                                        }
+                                    }
+                                }
+
+                                println!("Dependency chain:");
+                                print_deps_chain(&db, "    ", config, &mut Default::default());
+
+                                let requires = db.config_opt_requires(config);
+                                if !requires.is_empty() {
+                                    println!("Required options:");
+                                    for config_opt in &requires {
+                                        println!("    {}", config_opt);
+                                    }
+                                }
+                            }
                             if !config_data.sources.is_empty() {
                                 println!("Sources:");
                                 for source in &config_data.sources {
                                     println!("    {}", source.display());
                                     if let Some(source_data) = db.source(source) {
-                                        print_source_data("        ", source_data);
+                                        print_source_data(
+                                            "        ",
+                                            source_data,
+                                            db.source_module(source),
+                                        );
                                     }
                                 }
                             } else {
@@ -173,14 +621,306 @@ async fn main() -> Result<()> {
                         }
                     }
 
+                    Cmd::Acpi { id }
+                    | Cmd::Pci { id }
+                    | Cmd::Usb { id }
+                    | Cmd::I2c { id }
+                    | Cmd::Spi { id }
+                    | Cmd::Platform { id } => {
+                        let bus = match cmd {
+                            Cmd::Acpi { .. } => DeviceBus::Acpi,
+                            Cmd::Pci { .. } => DeviceBus::Pci,
+                            Cmd::Usb { .. } => DeviceBus::Usb,
+                            Cmd::I2c { .. } => DeviceBus::I2c,
+                            Cmd::Spi { .. } => DeviceBus::Spi,
+                            _ => DeviceBus::Platform,
+                        };
+                        let mut found = false;
+                        for (device, device_data) in db.find_devices(bus, id) {
+                            found = true;
+                            println!("Device: {} ({})", device, bus);
+                            println!("Sources:");
+                            for source in &device_data.sources {
+                                println!("    {}", source.display());
+                                if let Some(source_data) = db.source(source) {
+                                    print_source_data(
+                                        "        ",
+                                        source_data,
+                                        db.source_module(source),
+                                    );
+                                }
+                            }
+                        }
+                        if !found {
+                            eprintln!("Device \"{}\" ({}) not found!", id, bus);
+                        }
+                    }
+
+                    Cmd::Modalias { modalias } => {
+                        let modaliases = if let Some(modalias) = modalias {
+                            vec![modalias.clone()]
+                        } else {
+                            std::io::stdin()
+                                .lines()
+                                .collect::<std::io::Result<Vec<_>>>()?
+                        };
+
+                        for modalias in &modaliases {
+                            let modalias = modalias.trim();
+                            if modalias.is_empty() {
+                                continue;
+                            }
+                            let sources = db.resolve_modalias(modalias);
+                            if sources.is_empty() {
+                                eprintln!("No drivers found for modalias \"{}\"!", modalias);
+                                continue;
+                            }
+                            println!("Modalias: {}", modalias);
+                            for source in sources {
+                                println!("    {}", source.display());
+                                if let Some(source_data) = db.source(source) {
+                                    print_source_data(
+                                        "        ",
+                                        source_data,
+                                        db.source_module(source),
+                                    );
+                                }
+                            }
+                        }
+                    }
+
+                    Cmd::Dts { path } => {
+                        if db.dt_source(path).is_some() {
+                            let mut missing = Vec::default();
+
+                            println!("Compatible strings:");
+                            for compat in db.dt_compat_strs(path) {
+                                let sources = db.compat_str_sources(compat);
+                                if sources.is_empty() {
+                                    missing.push(compat);
+                                    continue;
+                                }
+                                println!("    {}", compat);
+                                for source in sources {
+                                    println!("        {}", source.display());
+                                    if let Some(source_data) = db.source(source) {
+                                        if !source_data.condition.is_true() {
+                                            println!(
+                                                "            Build condition: {}",
+                                                source_data.condition
+                                            );
+                                        }
+                                        if !source_data.config_opts.is_empty() {
+                                            println!("            Configuration options:");
+                                            for config_opt in &source_data.config_opts {
+                                                println!("                {}", config_opt);
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+
+                            if !missing.is_empty() {
+                                println!("Compatible strings without drivers:");
+                                for compat in missing {
+                                    println!("    {}", compat);
+                                }
+                            }
+                        } else {
+                            eprintln!("Devicetree source \"{}\" not found!", path.display());
+                        }
+                    }
+
+                    Cmd::Bindings => {
+                        println!("Compatible strings without bindings:");
+                        for compat in db.compat_strs_without_bindings() {
+                            println!("    {}", compat);
+                        }
+                        println!("Bindings without drivers:");
+                        for (binding, binding_data) in db.bindings_without_drivers() {
+                            println!("    {}", binding.display());
+                            for compat in &binding_data.compat_strs {
+                                println!("        {}", compat);
+                            }
+                        }
+                    }
+
+                    Cmd::Maintainers { query } => {
+                        let sections = db.maintainers_of(query);
+                        if sections.is_empty() {
+                            eprintln!("No maintainers found for \"{}\"!", query);
+                        }
+                        for section in sections {
+                            if section.status.is_empty() {
+                                println!("{}", section.name);
+                            } else {
+                                println!("{} ({})", section.name, section.status);
+                            }
+                            for (tag, values) in [
+                                ("M", &section.maintainers),
+                                ("R", &section.reviewers),
+                                ("L", &section.lists),
+                            ] {
+                                for value in values {
+                                    println!("    {}: {}", tag, value);
+                                }
+                            }
+                        }
+                    }
+
+                    Cmd::Symbol { symbol } => {
+                        let mut found = false;
+                        for (source, source_data, export_data) in db.symbol_exports(symbol) {
+                            found = true;
+                            println!("Exported by: {}", source.display());
+                            println!(
+                                "    License: {}",
+                                if export_data.gpl { "GPL only" } else { "any" }
+                            );
+                            if !export_data.namespace.is_empty() {
+                                println!("    Namespace: {}", export_data.namespace);
+                            }
+                            if !source_data.condition.is_true() {
+                                println!("    Build condition: {}", source_data.condition);
+                            }
+                            if !source_data.config_opts.is_empty() {
+                                println!("    Configuration options:");
+                                for config_opt in &source_data.config_opts {
+                                    println!("        {}", config_opt);
+                                }
+                            }
+                        }
+                        if !found {
+                            eprintln!("Exported symbol \"{}\" not found!", symbol);
+                        }
+                    }
+
+                    Cmd::Deps { source } => {
+                        let sources = if db.source(source).is_some() {
+                            vec![std::path::Path::new(source)]
+                        } else if let Some((_, kmod_data)) = db.kmod(source) {
+                            kmod_data
+                                .sources
+                                .iter()
+                                .map(|path| path.as_path())
+                                .collect()
+                        } else {
+                            // find sources by module name
+                            db.sources
+                                .iter()
+                                .filter(|(path, source_data)| {
+                                    path.file_stem()
+                                        .map(|stem| stem == source.as_str())
+                                        .unwrap_or(false)
+                                        || source_data
+                                            .module
+                                            .as_ref()
+                                            .map(|module| module.name == *source)
+                                            .unwrap_or(false)
+                                })
+                                .map(|(path, _)| path.as_path())
+                                .collect()
+                        };
+
+                        if sources.is_empty() {
+                            eprintln!("Source or module \"{}\" not found!", source);
+                        }
+
+                        for source in sources {
+                            println!("Source: {}", source.display());
+
+                            let deps = db.module_deps(source);
+                            if !deps.is_empty() {
+                                println!("Depends on:");
+                                for (module, symbols) in deps {
+                                    println!("    {}", module);
+                                    println!("        Symbols:");
+                                    for (symbol, exporter) in symbols {
+                                        println!("            {} ({})", symbol, exporter.display());
+                                    }
+                                }
+                            }
+
+                            let config_opts = db.source_deps_config_opts(source);
+                            if !config_opts.is_empty() {
+                                println!("Required configuration options:");
+                                for config_opt in config_opts {
+                                    println!("    {}", config_opt);
+                                }
+                            }
+
+                            let violations = db.gpl_violations(source);
+                            if !violations.is_empty() {
+                                println!("GPL-only symbols used by non-GPL module:");
+                                for (symbol, exporter) in violations {
+                                    println!("    {} (exported by {})", symbol, exporter.display());
+                                }
+                            }
+                        }
+                    }
+
+                    Cmd::Module { module } => {
+                        let kmods = if let Some(kmod) = db.kmod(module) {
+                            vec![kmod]
+                        } else {
+                            db.kmods_of_source(module).collect()
+                        };
+
+                        if kmods.is_empty() {
+                            eprintln!("Module \"{}\" not found!", module);
+                        }
+
+                        for (kmod, kmod_data) in kmods {
+                            println!("Module: {}", KmodData::name_of(kmod.to_string_lossy()));
+                            print_kmod_data("    ", kmod, kmod_data);
+                            print_module_data("    ", &db.kmod_module(kmod));
+                        }
+                    }
+
                     Cmd::Source { source } => {
-                        if let Some(source_data) = db.source(&source) {
+                        if let Some(source_data) = db.source(source) {
                             println!("Source: {}", source.display());
-                            print_source_data("    ", source_data);
+                            print_source_data("    ", source_data, db.source_module(source));
+                            for (kmod, _) in db.kmods_of_source(&source) {
+                                println!(
+                                    "    Kernel module: {}",
+                                    KmodData::name_of(kmod.to_string_lossy())
+                                );
+                            }
                         } else {
                             eprintln!("Source file \"{}\" not found!", source.display());
                         }
                     }
+
+                    Cmd::Includes { source } => {
+                        let source = db
+                            .find_header(source)
+                            .map(|(header, _)| header)
+                            .unwrap_or(source);
+                        if let Some(includes) = db.includes_of(source) {
+                            for include in includes {
+                                println!("{}", include.display());
+                            }
+                        } else {
+                            eprintln!("Source file \"{}\" not found!", source.display());
+                        }
+                    }
+
+                    Cmd::IncludedBy { header, recursive } => {
+                        if let Some((header, header_data)) = db.find_header(header) {
+                            if *recursive {
+                                for path in db.included_by_all(header) {
+                                    println!("{}", path.display());
+                                }
+                            } else {
+                                for path in &header_data.included_by {
+                                    println!("{}", path.display());
+                                }
+                            }
+                        } else {
+                            eprintln!("Header file \"{}\" not found!", header.display());
+                        }
+                    }
                 }
             } else {
                 eprintln!("Index does not exists!");
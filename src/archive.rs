@@ -0,0 +1,143 @@
+use crate::{FileMgr, FileStamp, MemTree, Path, PathBuf, Result};
+use std::io::{BufReader, Read};
+
+/// Extensions of supported archives
+const ARCHIVE_EXTENSIONS: &[&str] = &[
+    ".tar", ".tar.gz", ".tgz", ".tar.xz", ".txz", ".tar.zst", ".tzst",
+];
+
+/// Extensions of files which is needed for indexing
+const INDEXED_EXTENSIONS: &[&str] = &["c", "h", "rs", "S", "dts", "dtsi", "yaml"];
+
+/// Check that path looks like supported archive (like `linux-6.6.tar.xz`)
+pub fn is_archive(path: impl AsRef<Path>) -> bool {
+    let name = path.as_ref().to_string_lossy();
+
+    ARCHIVE_EXTENSIONS.iter().any(|ext| name.ends_with(ext))
+}
+
+impl FileMgr {
+    /// Create file manager instance using tar archive (optionally compressed)
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "archive")))]
+    pub async fn from_archive(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+
+        Ok(Self::from_tree(
+            tokio::task::spawn_blocking(move || MemTree::from_archive(path)).await??,
+        ))
+    }
+}
+
+impl MemTree {
+    /// Load files needed for indexing from tar archive
+    ///
+    /// Compression (gzip, xz or zstd) detected by magic bytes. Common top-level directory
+    /// (like `linux-6.6/`) is stripped. Only kbuild files, Kconfig files, sources, devicetree
+    /// sources, binding schemas and MAINTAINERS is loaded to reduce memory usage.
+    pub fn from_archive(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let mut file = BufReader::new(std::fs::File::open(&path)?);
+        let mut magic = [0u8; 6];
+        let len = read_magic(&mut file, &mut magic)?;
+        let magic = &magic[..len];
+        let reader = std::io::Cursor::new(magic.to_vec()).chain(file);
+
+        let reader: Box<dyn Read> = if magic.starts_with(&[0x1f, 0x8b]) {
+            Box::new(flate2::read::MultiGzDecoder::new(reader))
+        } else if magic.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Box::new(xz2::read::XzDecoder::new_multi_decoder(reader))
+        } else if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Box::new(zstd::stream::read::Decoder::new(reader)?)
+        } else {
+            Box::new(reader)
+        };
+
+        let mut files = Vec::default();
+
+        for entry in tar::Archive::new(reader).entries()? {
+            let mut entry = entry?;
+
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+
+            let name = entry.path()?.into_owned();
+
+            if !is_indexed(&name) {
+                continue;
+            }
+
+            let mut data = Vec::with_capacity(entry.size() as _);
+            entry.read_to_end(&mut data)?;
+            files.push((name, data));
+        }
+
+        // strip common top-level directory
+        let prefix = files
+            .first()
+            .and_then(|(name, _)| name.iter().next())
+            .map(PathBuf::from)
+            .filter(|prefix| {
+                files
+                    .iter()
+                    .all(|(name, _)| name.starts_with(prefix) && name != prefix)
+            });
+
+        log::debug!(
+            "Loaded {} files from archive {}",
+            files.len(),
+            path.display()
+        );
+
+        // hash of loaded files is used as tree id to cache index like for git revisions
+        let mut hash = FileStamp::hash_of(&[]);
+        let mut tree = Self::new(path);
+
+        for (name, data) in files {
+            let name = match &prefix {
+                Some(prefix) => name.strip_prefix(prefix)?.to_path_buf(),
+                None => name,
+            };
+            hash = FileStamp::hash_update(hash, name.to_string_lossy().as_bytes());
+            hash = FileStamp::hash_update(hash, &data);
+            tree.add_file(name, data);
+        }
+
+        tree = tree.with_tree_id(format!("{:016x}", hash));
+
+        Ok(tree)
+    }
+}
+
+/// Read first bytes of file to detect compression
+fn read_magic(reader: &mut impl Read, magic: &mut [u8]) -> Result<usize> {
+    let mut len = 0;
+
+    while len < magic.len() {
+        let read = reader.read(&mut magic[len..])?;
+        if read == 0 {
+            break;
+        }
+        len += read;
+    }
+
+    Ok(len)
+}
+
+/// Check that file is needed for indexing
+fn is_indexed(path: &Path) -> bool {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+
+    name.starts_with("Kbuild")
+        || name.starts_with("Makefile")
+        || name.starts_with("Kconfig")
+        || name == "MAINTAINERS"
+        || path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| INDEXED_EXTENSIONS.contains(&ext))
+            .unwrap_or(false)
+}
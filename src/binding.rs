@@ -0,0 +1,268 @@
+use crate::{BindingData, FileMgr, MetaData, Path, Result};
+use tokio::io::AsyncReadExt;
+
+/// Root directory of devicetree binding schemas
+const BINDINGS_DIR: &str = "Documentation/devicetree/bindings";
+
+impl MetaData {
+    /// Index devicetree binding schemas
+    pub(crate) async fn index_bindings(&mut self, filemgr: &FileMgr) -> Result<()> {
+        if !filemgr.dir_exists(BINDINGS_DIR).await? {
+            log::warn!("Missing devicetree bindings directory");
+            return Ok(());
+        }
+
+        let mut dirs = vec![Path::new(BINDINGS_DIR).to_path_buf()];
+
+        while let Some(dir) = dirs.pop() {
+            for path in filemgr.list_dir(&dir).await? {
+                if filemgr.dir_exists(&path).await? {
+                    dirs.push(path);
+                } else if path.extension().map(|ext| ext == "yaml").unwrap_or(false) {
+                    let binding = BindingData::from_yaml(filemgr, &path).await?;
+                    if !binding.compat_strs.is_empty() {
+                        self.bindings.insert(path, binding);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl BindingData {
+    /// Parse devicetree binding schema
+    ///
+    /// Only subset of YAML which is used in binding schemas is supported.
+    pub async fn from_yaml(filemgr: &FileMgr, path: impl AsRef<Path>) -> Result<Self> {
+        let mut file = filemgr.open(path).await?;
+        let mut src = String::new();
+
+        file.read_to_string(&mut src).await?;
+
+        Ok(Self::parse(&src))
+    }
+
+    fn parse(src: &str) -> Self {
+        let mut result = Self::default();
+        let lines = Lines::new(src);
+
+        // current top-level key
+        let mut section = "";
+        // indent of direct children of top-level key
+        let mut section_indent = None;
+        // indent of `compatible` property
+        let mut compatible = None;
+
+        for (indent, line) in lines {
+            if indent == 0 {
+                section_indent = None;
+                compatible = None;
+                let (key, value) = split_key(line);
+                section = key.unwrap_or_default();
+                match section {
+                    "$id" => result.id = unquote(value).into(),
+                    "title" if !value.starts_with(['|', '>']) => {
+                        result.title = unquote(value).into()
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+
+            let child_indent = *section_indent.get_or_insert(indent);
+
+            match section {
+                "maintainers" => {
+                    if let Some(value) = line.strip_prefix("- ") {
+                        result.maintainers.push(unquote(value).into());
+                    }
+                }
+                "properties" => {
+                    if let Some(compat_indent) = compatible {
+                        if indent <= compat_indent {
+                            compatible = None;
+                        }
+                    }
+
+                    if let Some(compat_indent) = compatible {
+                        if indent > compat_indent {
+                            result.add_compat_values(line);
+                        }
+                    } else if indent == child_indent && split_key(line).0 == Some("compatible") {
+                        compatible = Some(indent);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        result
+    }
+
+    /// Extract compatible strings from line of `compatible` property schema
+    fn add_compat_values(&mut self, line: &str) {
+        let line = line.trim_start_matches(|c: char| c == '-' || c.is_whitespace());
+
+        match split_key(line) {
+            (Some("const"), value) => self.add_compat(value),
+            (Some("enum"), value) => {
+                if let Some(values) = value.strip_prefix('[') {
+                    for value in values.trim_end_matches(']').split(',') {
+                        self.add_compat(value);
+                    }
+                }
+            }
+            // items of multi-line enum
+            (None, value) => self.add_compat(value),
+            _ => {}
+        }
+    }
+
+    fn add_compat(&mut self, value: &str) {
+        let value = unquote(value);
+
+        // skip patterns and empty values
+        if !value.is_empty()
+            && !value.contains(|c: char| c.is_whitespace() || "^$*+?()[]{}|\\".contains(c))
+            && !self.compat_strs.iter().any(|compat| compat == value)
+        {
+            self.compat_strs.push(value.into());
+        }
+    }
+}
+
+/// Iterator over significant lines of YAML document with indents
+///
+/// Comments, empty lines and block scalars (`|` and `>`) are skipped.
+struct Lines<'s> {
+    lines: core::str::Lines<'s>,
+    /// Indent of key which has block scalar value
+    block: Option<usize>,
+}
+
+impl<'s> Lines<'s> {
+    fn new(src: &'s str) -> Self {
+        Self {
+            lines: src.lines(),
+            block: None,
+        }
+    }
+}
+
+impl<'s> Iterator for Lines<'s> {
+    type Item = (usize, &'s str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = self.lines.next()?;
+            let trimmed = line.trim();
+
+            if trimmed.is_empty() || trimmed.starts_with('#') || trimmed == "---" {
+                continue;
+            }
+
+            let indent = line.len() - line.trim_start().len();
+
+            if let Some(block) = self.block {
+                if indent > block {
+                    continue;
+                }
+                self.block = None;
+            }
+
+            let line = strip_comment(trimmed);
+
+            if let (Some(_), "|" | "|-" | "|+" | ">" | ">-" | ">+") = split_key(line) {
+                self.block = Some(indent);
+            }
+
+            return Some((indent, line));
+        }
+    }
+}
+
+/// Split `key: value` line
+fn split_key(line: &str) -> (Option<&str>, &str) {
+    match line.split_once(':') {
+        Some((key, value))
+            if (value.is_empty() || value.starts_with(' ')) && !key.starts_with(['"', '\'']) =>
+        {
+            (Some(key.trim()), value.trim())
+        }
+        _ => (None, line.trim()),
+    }
+}
+
+/// Remove trailing comment
+fn strip_comment(line: &str) -> &str {
+    match line.find(" #") {
+        Some(pos) if !line[..pos].contains(['"', '\'']) => line[..pos].trim_end(),
+        _ => line,
+    }
+}
+
+/// Remove quotes around value
+fn unquote(value: &str) -> &str {
+    let value = value.trim();
+
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .or_else(|| {
+            value
+                .strip_prefix('\'')
+                .and_then(|value| value.strip_suffix('\''))
+        })
+        .unwrap_or(value)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::MemTree;
+
+    #[tokio::test]
+    async fn bindings_and_drivers() {
+        let tree = MemTree::new("linux");
+
+        tree.add_file(
+            "Documentation/devicetree/bindings/misc/acme,foo.yaml",
+            "$id: http://devicetree.org/schemas/misc/acme,foo.yaml#\ntitle: Acme Foo\nmaintainers:\n  - Jane Doe <jane@example.com>\nproperties:\n  compatible:\n    const: acme,foo\n",
+        );
+        tree.add_file(
+            "Documentation/devicetree/bindings/misc/acme,bar.yaml",
+            "title: Acme Bar\nproperties:\n  compatible:\n    const: acme,bar\n",
+        );
+
+        let mut metadata = MetaData::default();
+        metadata
+            .index_bindings(&FileMgr::from_tree(tree))
+            .await
+            .unwrap();
+        for compat in ["acme,foo", "acme,baz"] {
+            metadata
+                .source_mut("drivers/foo/foo.c")
+                .compat_strs
+                .insert(compat.into());
+        }
+        metadata.sync_with_sources();
+
+        let binding =
+            &metadata.bindings[Path::new("Documentation/devicetree/bindings/misc/acme,foo.yaml")];
+        assert_eq!(binding.title, "Acme Foo");
+        assert_eq!(binding.maintainers, ["Jane Doe <jane@example.com>"]);
+        assert_eq!(
+            metadata.compat_strs_without_bindings().collect::<Vec<_>>(),
+            ["acme,baz"]
+        );
+        assert_eq!(
+            metadata
+                .bindings_without_drivers()
+                .map(|(path, _)| path.to_str().unwrap())
+                .collect::<Vec<_>>(),
+            ["Documentation/devicetree/bindings/misc/acme,bar.yaml"]
+        );
+    }
+}
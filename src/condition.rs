@@ -0,0 +1,237 @@
+use crate::{DotConfig, Tristate};
+use std::collections::BTreeSet as Set;
+
+/// Build condition expression
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Condition {
+    /// Configuration option is enabled (`m` is constant which means enabled as module)
+    #[cfg_attr(feature = "serde", serde(rename = "s"))]
+    Symbol(String),
+
+    /// Configuration option has specified value (like `y` or `m`)
+    #[cfg_attr(feature = "serde", serde(rename = "e"))]
+    Equal(String, String),
+
+    /// Negation of condition
+    #[cfg_attr(feature = "serde", serde(rename = "n"))]
+    Not(Box<Condition>),
+
+    /// All conditions should be met (always true when empty)
+    #[cfg_attr(feature = "serde", serde(rename = "a"))]
+    And(Vec<Condition>),
+
+    /// Any of conditions should be met (never true when empty)
+    #[cfg_attr(feature = "serde", serde(rename = "o"))]
+    Or(Vec<Condition>),
+}
+
+impl Default for Condition {
+    fn default() -> Self {
+        Self::And(Vec::default())
+    }
+}
+
+impl core::ops::Not for Condition {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        match self {
+            Self::Not(condition) => *condition,
+            condition if condition.is_true() => Self::any([]),
+            condition if condition.is_false() => Self::default(),
+            condition => Self::Not(Box::new(condition)),
+        }
+    }
+}
+
+impl core::ops::BitAnd for Condition {
+    type Output = Self;
+
+    fn bitand(self, other: Self) -> Self {
+        match (self, other) {
+            (condition, other) if condition.is_false() || other.is_false() => Self::any([]),
+            (condition, other) if other.is_true() => condition,
+            (condition, other) if condition.is_true() => other,
+            (Self::And(mut conditions), Self::And(others)) => {
+                conditions.extend(others);
+                Self::And(conditions)
+            }
+            (Self::And(mut conditions), other) => {
+                conditions.push(other);
+                Self::And(conditions)
+            }
+            (condition, Self::And(mut others)) => {
+                others.insert(0, condition);
+                Self::And(others)
+            }
+            (condition, other) => Self::And(vec![condition, other]),
+        }
+    }
+}
+
+impl core::ops::BitAndAssign for Condition {
+    fn bitand_assign(&mut self, other: Self) {
+        *self = core::mem::take(self) & other;
+    }
+}
+
+impl core::fmt::Display for Condition {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        fn fmt_list(
+            f: &mut core::fmt::Formatter,
+            conditions: &[Condition],
+            op: &str,
+        ) -> core::fmt::Result {
+            for (index, condition) in conditions.iter().enumerate() {
+                if index > 0 {
+                    write!(f, " {} ", op)?;
+                }
+                if condition.is_compound() {
+                    write!(f, "({})", condition)?;
+                } else {
+                    write!(f, "{}", condition)?;
+                }
+            }
+            Ok(())
+        }
+
+        match self {
+            Self::Symbol(name) => write!(f, "{}", name),
+            Self::Equal(name, value) => write!(f, "{}={}", name, value),
+            Self::Not(condition) if condition.is_compound() => write!(f, "!({})", condition),
+            Self::Not(condition) => write!(f, "!{}", condition),
+            Self::And(conditions) if conditions.is_empty() => f.write_str("y"),
+            Self::Or(conditions) if conditions.is_empty() => f.write_str("n"),
+            Self::And(conditions) => fmt_list(f, conditions, "&&"),
+            Self::Or(conditions) => fmt_list(f, conditions, "||"),
+        }
+    }
+}
+
+impl Condition {
+    /// Create condition which is met when all of conditions are met
+    pub fn all(conditions: impl IntoIterator<Item = Self>) -> Self {
+        conditions
+            .into_iter()
+            .fold(Self::default(), |result, condition| result & condition)
+    }
+
+    /// Create condition which is met when any of conditions is met
+    pub fn any(conditions: impl IntoIterator<Item = Self>) -> Self {
+        let mut conditions = conditions
+            .into_iter()
+            .filter(|condition| !condition.is_false())
+            .collect::<Vec<_>>();
+        if conditions.len() == 1 {
+            conditions.pop().unwrap()
+        } else {
+            Self::Or(conditions)
+        }
+    }
+
+    /// Check that condition is always met
+    pub fn is_true(&self) -> bool {
+        matches!(self, Self::And(conditions) if conditions.is_empty())
+    }
+
+    /// Check that condition is never met
+    pub fn is_false(&self) -> bool {
+        matches!(self, Self::Or(conditions) if conditions.is_empty())
+    }
+
+    fn is_compound(&self) -> bool {
+        matches!(self, Self::And(conditions) | Self::Or(conditions) if conditions.len() > 1)
+    }
+
+    /// Get all configuration options used in condition
+    pub fn symbols(&self) -> Set<&str> {
+        let mut symbols = Set::default();
+        self.collect_symbols(&mut symbols, true);
+        symbols
+    }
+
+    /// Get configuration options which should be enabled to meet condition
+    pub fn positive_symbols(&self) -> Set<&str> {
+        let mut symbols = Set::default();
+        self.collect_symbols(&mut symbols, false);
+        symbols
+    }
+
+    /// Get configuration options which should be enabled in any case to meet condition
+    ///
+    /// Unlike positive symbols the options of alternatives (`A || B`) is not included.
+    pub fn required_symbols(&self) -> Set<&str> {
+        let mut symbols = Set::default();
+        self.collect_required_symbols(&mut symbols);
+        symbols
+    }
+
+    fn collect_required_symbols<'a>(&'a self, symbols: &mut Set<&'a str>) {
+        match self {
+            Self::Symbol(name) if name != "m" => {
+                symbols.insert(name);
+            }
+            Self::Equal(name, value) if value != "n" => {
+                symbols.insert(name);
+            }
+            Self::And(conditions) => {
+                for condition in conditions {
+                    condition.collect_required_symbols(symbols);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn collect_symbols<'a>(&'a self, symbols: &mut Set<&'a str>, with_negated: bool) {
+        match self {
+            Self::Symbol(name) if name == "m" => {}
+            Self::Symbol(name) | Self::Equal(name, _) => {
+                symbols.insert(name);
+            }
+            Self::Not(condition) => {
+                if with_negated {
+                    condition.collect_symbols(symbols, with_negated);
+                }
+            }
+            Self::And(conditions) | Self::Or(conditions) => {
+                for condition in conditions {
+                    condition.collect_symbols(symbols, with_negated);
+                }
+            }
+        }
+    }
+
+    /// Evaluate condition using kernel configuration
+    pub fn eval(&self, config: &DotConfig) -> Tristate {
+        match self {
+            Self::Symbol(name) if name == "m" => Tristate::Module,
+            Self::Symbol(name) => config.tristate(name),
+            Self::Equal(name, value) => {
+                if config.value(name).unwrap_or_default() == value.as_str() {
+                    Tristate::Yes
+                } else {
+                    Tristate::No
+                }
+            }
+            Self::Not(condition) => {
+                if condition.eval(config) == Tristate::No {
+                    Tristate::Yes
+                } else {
+                    Tristate::No
+                }
+            }
+            Self::And(conditions) => conditions
+                .iter()
+                .map(|condition| condition.eval(config))
+                .min()
+                .unwrap_or(Tristate::Yes),
+            Self::Or(conditions) => conditions
+                .iter()
+                .map(|condition| condition.eval(config))
+                .max()
+                .unwrap_or(Tristate::No),
+        }
+    }
+}
@@ -0,0 +1,453 @@
+use crate::{
+    source::normalize_path, DtNodeData, DtSourceData, FileMgr, MetaData, Path, PathBuf, Result,
+};
+use std::collections::{BTreeMap as Map, BTreeSet as Set};
+use tokio::io::AsyncReadExt;
+
+/// Devicetree source file extensions
+const DT_EXTENSIONS: [&str; 2] = ["dts", "dtsi"];
+
+impl MetaData {
+    /// Index devicetree sources of all architectures (`arch/*/boot/dts`)
+    pub(crate) async fn index_devicetree(&mut self, filemgr: &FileMgr) -> Result<()> {
+        let mut dirs = Vec::default();
+
+        for arch in filemgr.list_dir("arch").await? {
+            let dir = arch.join("boot/dts");
+            if filemgr.dir_exists(&dir).await? {
+                dirs.push(dir);
+            }
+        }
+
+        while let Some(dir) = dirs.pop() {
+            for path in filemgr.list_dir(&dir).await? {
+                if filemgr.dir_exists(&path).await? {
+                    dirs.push(path);
+                } else if path
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .map(|ext| DT_EXTENSIONS.contains(&ext))
+                    .unwrap_or(false)
+                {
+                    let dt_source = DtSourceData::from_dts(filemgr, &path).await?;
+                    self.dt_sources.insert(path, dt_source);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get compatible strings used by devicetree source including all included files
+    ///
+    /// Files is applied in include order so `&label` overrides of included nodes is taken
+    /// into account. Disabled nodes and children of disabled nodes is skipped.
+    pub fn dt_compat_strs(&self, dt_source: impl AsRef<Path>) -> Set<&String> {
+        let mut tree = DtTree::default();
+        let mut visited = Set::default();
+
+        self.apply_dt_source(dt_source.as_ref(), &mut tree, &mut visited);
+
+        let disabled = tree
+            .nodes
+            .iter()
+            .filter(|(_, (_, status))| !is_dt_status_enabled(*status))
+            .map(|(node, _)| node.as_str())
+            .collect::<Set<_>>();
+
+        tree.nodes
+            .iter()
+            .filter(|(node, _)| {
+                !Path::new(node)
+                    .ancestors()
+                    .filter_map(|node| node.to_str())
+                    .any(|node| disabled.contains(node))
+            })
+            .flat_map(|(_, (compat_strs, _))| compat_strs.iter().copied().flatten())
+            .collect()
+    }
+
+    /// Apply nodes of devicetree source after nodes of its includes
+    fn apply_dt_source<'a>(
+        &'a self,
+        path: &Path,
+        tree: &mut DtTree<'a>,
+        visited: &mut Set<&'a PathBuf>,
+    ) {
+        let Some((path, dt_source_data)) = self.dt_sources.get_key_value(path) else {
+            return;
+        };
+
+        if !visited.insert(path) {
+            return;
+        }
+
+        for include in &dt_source_data.includes {
+            self.apply_dt_source(include, tree, visited);
+        }
+
+        for (label, node) in &dt_source_data.labels {
+            let node = tree.resolve(node);
+            tree.labels.insert(label, node);
+        }
+
+        // references goes after nodes by path to override them
+        let (refs, paths): (Vec<_>, Vec<_>) = dt_source_data
+            .nodes
+            .iter()
+            .partition(|(node, _)| node.starts_with('&'));
+
+        for (node, node_data) in paths.into_iter().chain(refs) {
+            let (compat_strs, status) = tree.nodes.entry(tree.resolve(node)).or_default();
+
+            if !node_data.compat_strs.is_empty() {
+                *compat_strs = Some(&node_data.compat_strs);
+            }
+            if node_data.status.is_some() {
+                *status = node_data.status.as_deref();
+            }
+        }
+    }
+}
+
+impl DtNodeData {
+    /// Check that node is not disabled
+    pub fn is_enabled(&self) -> bool {
+        is_dt_status_enabled(self.status.as_deref())
+    }
+}
+
+/// Check that devicetree node status means enabled node
+fn is_dt_status_enabled(status: Option<&str>) -> bool {
+    matches!(status, None | Some("okay" | "ok"))
+}
+
+/// Devicetree nodes with overrides of included files applied
+#[derive(Default)]
+struct DtTree<'a> {
+    /// Node paths by label
+    labels: Map<&'a str, String>,
+
+    /// Compatible strings and status by node path
+    nodes: Map<String, (Option<&'a Vec<String>>, Option<&'a str>)>,
+}
+
+impl DtTree<'_> {
+    /// Resolve node reference (like `&smmu` or `&smmu/child`) to node path
+    fn resolve(&self, node: &str) -> String {
+        node.strip_prefix('&')
+            .and_then(|node| {
+                let (label, child) = node.split_once('/').unwrap_or((node, ""));
+                let path = self.labels.get(label)?;
+                Some(if child.is_empty() {
+                    path.clone()
+                } else {
+                    format!("{}/{}", path.trim_end_matches('/'), child)
+                })
+            })
+            .unwrap_or_else(|| node.into())
+    }
+}
+
+impl DtSourceData {
+    /// Parse devicetree source file
+    pub async fn from_dts(filemgr: &FileMgr, path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let mut file = filemgr.open(&path).await?;
+        let mut src = String::new();
+
+        file.read_to_string(&mut src).await?;
+
+        let mut result = Self::default();
+        let mut body = String::with_capacity(src.len());
+
+        for line in src.lines() {
+            let trimmed = line.trim_start();
+            if let Some(name) = parse_include(trimmed) {
+                if let Some(include) = resolve_include(filemgr, path, name).await? {
+                    if !result.includes.contains(&include) {
+                        result.includes.push(include);
+                    }
+                }
+            } else if !is_directive(trimmed) {
+                body.push_str(line);
+                body.push('\n');
+            }
+        }
+
+        parse_nodes(&body, &mut result);
+
+        Ok(result)
+    }
+}
+
+/// Get included file name of `#include` or `/include/` directive
+fn parse_include(line: &str) -> Option<&str> {
+    let rest = if let Some(rest) = line.strip_prefix('#') {
+        rest.trim_start().strip_prefix("include")?
+    } else {
+        line.strip_prefix("/include/")?
+    }
+    .trim_start();
+
+    if let Some(name) = rest.strip_prefix('<') {
+        name.split_once('>').map(|(name, _)| name.trim())
+    } else if let Some(name) = rest.strip_prefix('"') {
+        name.split_once('"').map(|(name, _)| name.trim())
+    } else {
+        None
+    }
+}
+
+/// Check that line is preprocessor directive (not a property like `#address-cells`)
+fn is_directive(line: &str) -> bool {
+    line.strip_prefix('#')
+        .map(|rest| {
+            let word = rest
+                .trim_start()
+                .split(|c: char| !c.is_alphanumeric())
+                .next()
+                .unwrap_or_default();
+            matches!(
+                word,
+                "define" | "undef" | "if" | "ifdef" | "ifndef" | "elif" | "else" | "endif"
+            )
+        })
+        .unwrap_or(false)
+}
+
+/// Resolve path of included file
+///
+/// Search order follows dtc invocation in kernel: directory of including file,
+/// `arch/<arch>/boot/dts`, include prefixes (like `arm64/...`) and `include`.
+async fn resolve_include(filemgr: &FileMgr, path: &Path, name: &str) -> Result<Option<PathBuf>> {
+    let mut candidates = Vec::default();
+
+    if let Some(dir) = path.parent() {
+        candidates.push(dir.join(name));
+    }
+
+    // arch/<arch>/boot/dts
+    let dts_root = path.iter().take(4).collect::<PathBuf>();
+    candidates.push(dts_root.join(name));
+
+    // scripts/dtc/include-prefixes/<arch> links to arch/<arch>/boot/dts
+    let mut components = Path::new(name).iter();
+    if let Some(prefix) = components.next() {
+        candidates.push(
+            Path::new("arch")
+                .join(prefix)
+                .join("boot/dts")
+                .join(components.as_path()),
+        );
+    }
+
+    candidates.push(Path::new("include").join(name));
+
+    for candidate in candidates {
+        let candidate = normalize_path(candidate);
+        if filemgr.file_exists(&candidate).await? {
+            return Ok(Some(candidate));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Extract compatible strings, status and labels of nodes
+///
+/// Nodes identified by full path (like `/soc/iommu@d0000000`) or by reference (like `&smmu`).
+fn parse_nodes(src: &str, result: &mut DtSourceData) {
+    let mut path: Vec<String> = Vec::default();
+    let mut stmt = String::default();
+    let mut chars = src.chars().peekable();
+    let mut in_ref = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            // comments
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+                stmt.push(' ');
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                for c in chars.by_ref() {
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+                stmt.push(' ');
+            }
+            // strings
+            '"' => {
+                stmt.push(c);
+                while let Some(c) = chars.next() {
+                    stmt.push(c);
+                    match c {
+                        '\\' => stmt.extend(chars.next()),
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            // node reference by path: &{/path/to/node}
+            '{' if stmt.trim_end().ends_with('&') => {
+                stmt.push(c);
+                in_ref = true;
+            }
+            '}' if in_ref => {
+                stmt.push(c);
+                in_ref = false;
+            }
+            '{' => {
+                let name = node_name(&stmt);
+                let node = if name.starts_with('&') || name == "/" {
+                    name.trim_start_matches("&{").trim_end_matches('}').into()
+                } else {
+                    match path.last() {
+                        Some(parent) if parent.ends_with('/') => format!("{}{}", parent, name),
+                        Some(parent) => format!("{}/{}", parent, name),
+                        None => format!("/{}", name),
+                    }
+                };
+                for label in node_labels(&stmt) {
+                    result.labels.insert(label.into(), node.clone());
+                }
+                path.push(node);
+                stmt.clear();
+            }
+            '}' => {
+                path.pop();
+                stmt.clear();
+            }
+            ';' => {
+                if let Some(node) = path.last() {
+                    if let Some(compat_strs) = parse_property(&stmt, "compatible") {
+                        result.nodes.entry(node.clone()).or_default().compat_strs = compat_strs;
+                    } else if let Some(status) = parse_property(&stmt, "status") {
+                        result.nodes.entry(node.clone()).or_default().status =
+                            status.into_iter().next();
+                    }
+                }
+                stmt.clear();
+            }
+            c => stmt.push(c),
+        }
+    }
+}
+
+/// Get node name without labels (like `smmu: iommu@d0000000`)
+fn node_name(stmt: &str) -> &str {
+    stmt.split_whitespace()
+        .rfind(|word| !word.ends_with(':'))
+        .map(|name| name.rsplit(':').next().unwrap_or(name))
+        .unwrap_or_default()
+}
+
+/// Get labels of node (like `smmu` in `smmu: iommu@d0000000`)
+fn node_labels(stmt: &str) -> impl Iterator<Item = &str> {
+    stmt.split_whitespace()
+        .flat_map(|word| word.rsplit_once(':').map(|(labels, _)| labels.split(':')))
+        .flatten()
+        .filter(|label| !label.is_empty())
+}
+
+/// Parse strings of property (like `compatible` or `status`)
+fn parse_property(stmt: &str, property: &str) -> Option<Vec<String>> {
+    let (name, value) = stmt.split_once('=')?;
+
+    if name.trim() != property {
+        return None;
+    }
+
+    Some(
+        value
+            .split('"')
+            .skip(1)
+            .step_by(2)
+            .map(String::from)
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::MemTree;
+
+    #[tokio::test]
+    async fn status_overrides() {
+        let tree = MemTree::new("linux");
+
+        tree.add_file(
+            "arch/arm64/boot/dts/vendor/soc.dtsi",
+            r#"
+/ {
+    soc {
+        smmu: iommu@d0000000 {
+            compatible = "vendor,smmu";
+            status = "disabled";
+        };
+        uart0: serial@1000 {
+            compatible = "vendor,uart";
+        };
+        i2c: i2c@2000 {
+            compatible = "vendor,i2c";
+            status = "disabled";
+            sensor@48 {
+                compatible = "vendor,sensor";
+            };
+        };
+        gpu@3000 {
+            compatible = "vendor,gpu";
+            status = "disabled";
+        };
+    };
+};
+"#,
+        );
+        tree.add_file(
+            "arch/arm64/boot/dts/vendor/board.dts",
+            r#"
+/dts-v1/;
+#include "soc.dtsi"
+
+&smmu {
+    status = "okay";
+};
+
+&uart0 {
+    status = "disabled";
+};
+"#,
+        );
+
+        let filemgr = FileMgr::from_tree(tree);
+        let mut metadata = MetaData::default();
+        metadata.index_devicetree(&filemgr).await.unwrap();
+
+        let compat_strs = metadata
+            .dt_compat_strs("arch/arm64/boot/dts/vendor/board.dts")
+            .into_iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+
+        assert_eq!(compat_strs, ["vendor,smmu"]);
+    }
+
+    #[test]
+    fn labels() {
+        assert_eq!(
+            node_labels("smmu: smmu0:iommu@d0000000").collect::<Vec<_>>(),
+            ["smmu", "smmu0"]
+        );
+        assert_eq!(node_name("smmu: smmu0:iommu@d0000000"), "iommu@d0000000");
+    }
+}
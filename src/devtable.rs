@@ -0,0 +1,853 @@
+use crate::{DeviceBus, DeviceIdData, DeviceTableData, FileMgr, MetaData, Result};
+
+use clex::{Lexer, Token};
+use std::collections::BTreeMap as Map;
+use tokio::io::AsyncReadExt;
+
+/// Token slice with string value (for string literals)
+type Tok<'s> = (&'s str, Option<String>);
+
+/// Field value with prefix which should be prepended to symbolic value
+type Value<'t, 's> = (String, &'t [Tok<'s>]);
+
+/// Words which may be used in type casts of driver data
+const CAST_WORDS: &[&str] = &[
+    "void",
+    "const",
+    "unsigned",
+    "long",
+    "int",
+    "char",
+    "ulong",
+    "kernel_ulong_t",
+    "uintptr_t",
+];
+
+/// Header which defines symbolic PCI vendor and device identifiers
+const PCI_IDS_HEADER: &str = "include/linux/pci_ids.h";
+
+/// Header which defines symbolic USB class codes
+const USB_CH9_HEADER: &str = "include/uapi/linux/usb/ch9.h";
+
+/// Fields of USB device id structure which forms identifier with widths
+const USB_ID_FIELDS: [(&str, usize); 8] = [
+    ("idVendor", 4),
+    ("idProduct", 4),
+    ("bDeviceClass", 2),
+    ("bDeviceSubClass", 2),
+    ("bDeviceProtocol", 2),
+    ("bInterfaceClass", 2),
+    ("bInterfaceSubClass", 2),
+    ("bInterfaceProtocol", 2),
+];
+
+impl DeviceTableData {
+    /// Extract device match tables from C source
+    pub fn from_source(src: &str) -> Map<String, Self> {
+        let defines = parse_defines(src);
+
+        let tokens = Lexer::from(src)
+            .filter(|lexeme| lexeme.token != Token::Comment)
+            .map(|lexeme| {
+                let string = if lexeme.token == Token::String {
+                    lexeme.string()
+                } else {
+                    None
+                };
+                (lexeme.slice, string)
+            })
+            .collect::<Vec<_>>();
+
+        let mut tables = Map::default();
+        let mut exported = Vec::default();
+        let mut pos = 0;
+
+        while pos < tokens.len() {
+            match tokens[pos].0 {
+                // struct <bus>_device_id <name>[] = { ... };
+                "struct" => {
+                    if let Some(bus) = tokens
+                        .get(pos + 1)
+                        .and_then(|(slice, _)| DeviceBus::from_struct(slice))
+                    {
+                        if let Some((name, body, end)) = table_decl(&tokens, pos + 2) {
+                            let entries = entries(body)
+                                .into_iter()
+                                .filter_map(|entry| DeviceIdData::from_entry(bus, entry, &defines))
+                                .collect();
+                            tables.insert(
+                                name.into(),
+                                Self {
+                                    bus,
+                                    exported: false,
+                                    entries,
+                                },
+                            );
+                            pos = end;
+                        }
+                    }
+                }
+                // MODULE_DEVICE_TABLE(<bus>, <name>)
+                "MODULE_DEVICE_TABLE" => {
+                    if let [_, ("(", _), _, (",", _), (name, _), (")", _), ..] = &tokens[pos..] {
+                        exported.push(*name);
+                    }
+                }
+                _ => {}
+            }
+            pos += 1;
+        }
+
+        for name in exported {
+            if let Some(table) = tables.get_mut(name) {
+                table.exported = true;
+            }
+        }
+
+        tables
+    }
+}
+
+impl DeviceIdData {
+    /// Parse device match table entry
+    fn from_entry(bus: DeviceBus, entry: &[Tok<'_>], defines: &Map<&str, u32>) -> Option<Self> {
+        let mut values = Map::default();
+        let mut index = 0;
+
+        for (field, value) in fields(entry) {
+            if let Some(field) = field {
+                values.insert(field, (String::default(), value));
+                continue;
+            }
+            if let Some((fields, next)) = expand_macro(bus, value) {
+                values.extend(fields);
+                index = next;
+                continue;
+            }
+            if let Some(field) = bus.fields().get(index) {
+                values.insert(*field, (String::default(), value));
+            }
+            index += 1;
+        }
+
+        let id = match bus {
+            DeviceBus::Of => string_of(values.get("compatible")),
+            DeviceBus::Acpi => string_of(values.get("id")),
+            DeviceBus::I2c | DeviceBus::Spi | DeviceBus::Platform => string_of(values.get("name")),
+            DeviceBus::Pci => {
+                // zero vendor is sentinel like `{ 0, }`
+                let vendor =
+                    number_of(values.get("vendor"), defines, 4).filter(|vendor| vendor != "0000");
+                let device = number_of(values.get("device"), defines, 4);
+                let class = number_of(values.get("class"), defines, 6).map(|class| {
+                    let mask = match number_of(values.get("class_mask"), defines, 6) {
+                        Some(mask) => u32::from_str_radix(&mask, 16).unwrap_or(0xffffff),
+                        None => 0xffffff,
+                    };
+                    match u32::from_str_radix(&class, 16) {
+                        Ok(class) => masked_class(class, mask),
+                        // mask applied when class will be resolved
+                        Err(_) if class != "*" && mask != 0xffffff => {
+                            format!("{}&{:06x}", class, mask)
+                        }
+                        Err(_) => class,
+                    }
+                });
+
+                match (vendor, device, class) {
+                    (None, None, None) => String::default(),
+                    (vendor, device, class) => {
+                        let mut id = format!(
+                            "{}:{}",
+                            vendor.as_deref().unwrap_or("*"),
+                            device.as_deref().unwrap_or("*")
+                        );
+                        if let Some(class) = class.filter(|class| class != "*") {
+                            id.push(':');
+                            id.push_str(&class);
+                        }
+                        id
+                    }
+                }
+            }
+            // vendor:product:class:subclass:protocol:ifclass:ifsubclass:ifprotocol
+            DeviceBus::Usb => {
+                let mut parts = USB_ID_FIELDS
+                    .iter()
+                    .map(|(field, width)| number_of(values.get(field), defines, *width))
+                    .collect::<Vec<_>>();
+
+                while parts.len() > 2 && matches!(parts.last(), Some(None)) {
+                    parts.pop();
+                }
+
+                if parts.iter().all(Option::is_none) {
+                    String::default()
+                } else {
+                    parts
+                        .into_iter()
+                        .map(|part| part.unwrap_or_else(|| "*".into()))
+                        .collect::<Vec<_>>()
+                        .join(":")
+                }
+            }
+        };
+
+        if id.is_empty() {
+            // sentinel entry
+            return None;
+        }
+
+        let data = values
+            .get(bus.data_field())
+            .map(|(_, value)| ident_of(value))
+            .unwrap_or_default();
+
+        Some(Self { id, data })
+    }
+}
+
+impl MetaData {
+    /// Resolve symbolic PCI and USB identifiers using definitions from `pci_ids.h` and `ch9.h`
+    pub(crate) async fn resolve_device_ids(&mut self, filemgr: &FileMgr) -> Result<()> {
+        let mut srcs = Vec::default();
+
+        for header in [PCI_IDS_HEADER, USB_CH9_HEADER] {
+            if !filemgr.file_exists(header).await? {
+                log::warn!("Missing {} header", header);
+                continue;
+            }
+
+            let mut file = filemgr.open(header).await?;
+            let mut src = String::new();
+
+            file.read_to_string(&mut src).await?;
+            srcs.push(src);
+        }
+
+        let defines = srcs
+            .iter()
+            .flat_map(|src| parse_defines(src))
+            .collect::<Map<_, _>>();
+
+        for source_data in self.sources.values_mut() {
+            for table in source_data.device_tables.values_mut() {
+                if !matches!(table.bus, DeviceBus::Pci | DeviceBus::Usb) {
+                    continue;
+                }
+                for entry in &mut table.entries {
+                    entry.id = entry
+                        .id
+                        .split(':')
+                        .enumerate()
+                        .map(|(index, part)| resolve_id_part(table.bus, index, part, &defines))
+                        .collect::<Vec<_>>()
+                        .join(":");
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Resolve symbolic component of PCI or USB identifier
+///
+/// PCI class may have mask (like `PCI_CLASS_STORAGE_EXPRESS&ffff00`).
+fn resolve_id_part(bus: DeviceBus, index: usize, part: &str, defines: &Map<&str, u32>) -> String {
+    let width = match (bus, index) {
+        (DeviceBus::Pci, 2) => 6,
+        (DeviceBus::Usb, 2..) => 2,
+        _ => 4,
+    };
+    if part
+        .chars()
+        .all(|c| c.is_ascii_hexdigit() || matches!(c, '*' | '?'))
+    {
+        return part.into();
+    }
+
+    let (symbol, mask) = match part.split_once('&') {
+        Some((symbol, mask)) => (symbol, u32::from_str_radix(mask, 16).ok()),
+        None => (part, None),
+    };
+
+    match (eval_number(symbol, defines), mask) {
+        (Some(value), Some(mask)) => masked_class(value, mask),
+        (Some(value), None) => format!("{:01$x}", value, width),
+        (None, _) => part.into(),
+    }
+}
+
+/// Get hexadecimal representation of PCI class with masked out digits replaced by `?`
+fn masked_class(class: u32, mask: u32) -> String {
+    if mask & 0xffffff == 0 {
+        return "*".into();
+    }
+
+    (0..6)
+        .rev()
+        .map(|digit| {
+            let shift = digit * 4;
+            if (mask >> shift) & 0xf == 0xf {
+                char::from_digit((class >> shift) & 0xf, 16).unwrap()
+            } else {
+                '?'
+            }
+        })
+        .collect()
+}
+
+impl DeviceBus {
+    /// Positional fields of device id structure
+    fn fields(&self) -> &'static [&'static str] {
+        match self {
+            Self::Of => &["name", "type", "compatible", "data"],
+            Self::Acpi => &["id", "driver_data", "cls", "cls_msk"],
+            Self::Pci => &[
+                "vendor",
+                "device",
+                "subvendor",
+                "subdevice",
+                "class",
+                "class_mask",
+                "driver_data",
+                "override_only",
+            ],
+            Self::Usb => &[
+                "match_flags",
+                "idVendor",
+                "idProduct",
+                "bcdDevice_lo",
+                "bcdDevice_hi",
+                "bDeviceClass",
+                "bDeviceSubClass",
+                "bDeviceProtocol",
+                "bInterfaceClass",
+                "bInterfaceSubClass",
+                "bInterfaceProtocol",
+                "bInterfaceNumber",
+                "driver_info",
+            ],
+            Self::I2c | Self::Spi | Self::Platform => &["name", "driver_data"],
+        }
+    }
+
+    /// Field of device id structure which holds driver data
+    fn data_field(&self) -> &'static str {
+        match self {
+            Self::Of => "data",
+            Self::Usb => "driver_info",
+            _ => "driver_data",
+        }
+    }
+}
+
+/// Expand helper macros like `PCI_DEVICE(vendor, device)`
+///
+/// Returns initialized fields and index of next positional field.
+fn expand_macro<'t, 's>(
+    bus: DeviceBus,
+    value: &'t [Tok<'s>],
+) -> Option<(Vec<(&'static str, Value<'t, 's>)>, usize)> {
+    let (name, args) = match value {
+        [(name, _), ("(", _), args @ .., (")", _)] => (*name, fields(args)),
+        _ => return None,
+    };
+    let args = args.into_iter().map(|(_, value)| value).collect::<Vec<_>>();
+    let arg = |index: usize| args.get(index).copied().unwrap_or_default();
+    let plain = |index: usize| (String::default(), arg(index));
+    let symbol = |prefix: String, index: usize| (prefix, arg(index));
+    let vendor_name = arg(0).first().map(|(slice, _)| *slice).unwrap_or_default();
+
+    Some(match (bus, name) {
+        (DeviceBus::Pci, "PCI_DEVICE" | "PCI_DEVICE_SUB") => {
+            (vec![("vendor", plain(0)), ("device", plain(1))], 4)
+        }
+        (DeviceBus::Pci, "PCI_VDEVICE" | "PCI_VDEVICE_SUB") => (
+            vec![
+                ("vendor", symbol("PCI_VENDOR_ID_".into(), 0)),
+                ("device", plain(1)),
+            ],
+            6,
+        ),
+        (DeviceBus::Pci, "PCI_DEVICE_DATA") => (
+            vec![
+                ("vendor", symbol("PCI_VENDOR_ID_".into(), 0)),
+                (
+                    "device",
+                    symbol(format!("PCI_DEVICE_ID_{}_", vendor_name), 1),
+                ),
+                ("driver_data", plain(2)),
+            ],
+            7,
+        ),
+        (DeviceBus::Pci, "PCI_DEVICE_CLASS") => {
+            (vec![("class", plain(0)), ("class_mask", plain(1))], 6)
+        }
+        (
+            DeviceBus::Usb,
+            "USB_DEVICE"
+            | "USB_DEVICE_VER"
+            | "USB_DEVICE_INTERFACE_CLASS"
+            | "USB_DEVICE_INTERFACE_PROTOCOL"
+            | "USB_DEVICE_INTERFACE_NUMBER"
+            | "USB_DEVICE_AND_INTERFACE_INFO",
+        ) => {
+            let mut fields = vec![("idVendor", plain(0)), ("idProduct", plain(1))];
+            match name {
+                "USB_DEVICE_INTERFACE_CLASS" => fields.push(("bInterfaceClass", plain(2))),
+                "USB_DEVICE_INTERFACE_PROTOCOL" => fields.push(("bInterfaceProtocol", plain(2))),
+                "USB_DEVICE_AND_INTERFACE_INFO" => fields.extend([
+                    ("bInterfaceClass", plain(2)),
+                    ("bInterfaceSubClass", plain(3)),
+                    ("bInterfaceProtocol", plain(4)),
+                ]),
+                _ => {}
+            }
+            (fields, 12)
+        }
+        (DeviceBus::Usb, "USB_VENDOR_AND_INTERFACE_INFO") => (
+            vec![
+                ("idVendor", plain(0)),
+                ("bInterfaceClass", plain(1)),
+                ("bInterfaceSubClass", plain(2)),
+                ("bInterfaceProtocol", plain(3)),
+            ],
+            12,
+        ),
+        (DeviceBus::Usb, "USB_DEVICE_INFO") => (
+            vec![
+                ("bDeviceClass", plain(0)),
+                ("bDeviceSubClass", plain(1)),
+                ("bDeviceProtocol", plain(2)),
+            ],
+            12,
+        ),
+        (DeviceBus::Usb, "USB_INTERFACE_INFO") => (
+            vec![
+                ("bInterfaceClass", plain(0)),
+                ("bInterfaceSubClass", plain(1)),
+                ("bInterfaceProtocol", plain(2)),
+            ],
+            12,
+        ),
+        (DeviceBus::Usb, "USB_INTERFACE_CLASS") => (vec![("bInterfaceClass", plain(0))], 12),
+        _ => return None,
+    })
+}
+
+/// Find table declaration
+///
+/// Returns table name, table body tokens and position of closing brace.
+fn table_decl<'t, 's>(
+    tokens: &'t [Tok<'s>],
+    start: usize,
+) -> Option<(&'s str, &'t [Tok<'s>], usize)> {
+    let mut name = None;
+    let mut pos = start;
+
+    while let Some((slice, _)) = tokens.get(pos) {
+        match *slice {
+            "[" if name.is_none() => name = Some(tokens[pos - 1].0),
+            "=" => break,
+            // not a table definition
+            ";" | "(" | ")" | "*" | "{" | "," => return None,
+            _ => {}
+        }
+        pos += 1;
+    }
+
+    let name = name.filter(|name| is_ident(name))?;
+
+    if tokens.get(pos + 1)?.0 != "{" {
+        return None;
+    }
+
+    let mut depth = 0usize;
+
+    for (idx, (slice, _)) in tokens.iter().enumerate().skip(pos + 1) {
+        match *slice {
+            "{" => depth += 1,
+            "}" => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some((name, &tokens[pos + 2..idx], idx));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Split table body into entries
+fn entries<'t, 's>(body: &'t [Tok<'s>]) -> Vec<&'t [Tok<'s>]> {
+    let mut entries = Vec::default();
+    let mut depth = 0usize;
+    let mut start = 0;
+
+    for (idx, (slice, _)) in body.iter().enumerate() {
+        match *slice {
+            "{" => {
+                if depth == 0 {
+                    start = idx + 1;
+                }
+                depth += 1;
+            }
+            "}" => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    entries.push(&body[start..idx]);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    entries
+}
+
+/// Split entry into fields
+///
+/// Designated initializers (`.field = value`) returns with field name.
+fn fields<'t, 's>(entry: &'t [Tok<'s>]) -> Vec<(Option<&'s str>, &'t [Tok<'s>])> {
+    let mut fields = Vec::default();
+    let mut depth = 0usize;
+    let mut start = 0;
+
+    for idx in 0..=entry.len() {
+        match entry.get(idx).map(|(slice, _)| *slice) {
+            Some("{" | "(" | "[") => depth += 1,
+            Some("}" | ")" | "]") => depth = depth.saturating_sub(1),
+            Some(",") | None if depth == 0 => {
+                let field = &entry[start..idx];
+                start = idx + 1;
+
+                match field {
+                    [] => {}
+                    [(".", _), (name, _), ("=", _), value @ ..] => {
+                        fields.push((Some(*name), value))
+                    }
+                    value => fields.push((None, value)),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fields
+}
+
+/// Extract integer constants defined using `#define`
+fn parse_defines(src: &str) -> Map<&str, u32> {
+    src.lines()
+        .filter_map(|line| {
+            let line = line
+                .trim_start()
+                .strip_prefix('#')?
+                .trim_start()
+                .strip_prefix("define")?;
+            let mut words = line.split_whitespace();
+            let name = words.next()?;
+            let value = parse_number(words.next()?.trim_matches(|c| c == '(' || c == ')'))?;
+
+            Some((name, value))
+        })
+        .collect()
+}
+
+/// Parse C integer literal
+fn parse_number(literal: &str) -> Option<u32> {
+    let literal = literal.trim_end_matches(['u', 'U', 'l', 'L']);
+
+    if let Some(digits) = literal
+        .strip_prefix("0x")
+        .or_else(|| literal.strip_prefix("0X"))
+    {
+        u32::from_str_radix(digits, 16).ok()
+    } else {
+        literal.parse().ok()
+    }
+}
+
+fn is_ident(slice: &str) -> bool {
+    slice.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && slice.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Get concatenated string literals
+fn string_of(value: Option<&Value<'_, '_>>) -> String {
+    value
+        .map(|(_, value)| {
+            value
+                .iter()
+                .filter_map(|(_, string)| string.as_deref())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Get hexadecimal representation of numeric identifier
+///
+/// Wildcards (like `PCI_ANY_ID`) turns into `*` and unknown symbols returns as is.
+/// Numbers padded with zeros to given width.
+fn number_of(
+    value: Option<&Value<'_, '_>>,
+    defines: &Map<&str, u32>,
+    width: usize,
+) -> Option<String> {
+    let (prefix, value) = value?;
+    let literal = value
+        .iter()
+        .map(|(slice, _)| *slice)
+        .filter(|slice| !matches!(*slice, "(" | ")"))
+        .collect::<String>();
+
+    if literal.is_empty() {
+        return None;
+    }
+
+    if matches!(literal.as_str(), "PCI_ANY_ID" | "~0") {
+        return Some("*".into());
+    }
+
+    let symbol = if parse_number(&literal).is_some() {
+        literal
+    } else {
+        format!("{}{}", prefix, literal)
+    };
+
+    Some(match eval_number(&symbol, defines) {
+        Some(value) => format!("{:01$x}", value, width),
+        None => symbol,
+    })
+}
+
+/// Evaluate numeric literal or defined symbol which may be shifted (like `PCI_CLASS_STORAGE_RAID<<8`)
+fn eval_number(expr: &str, defines: &Map<&str, u32>) -> Option<u32> {
+    let (value, shift) = match expr.split_once("<<") {
+        Some((value, shift)) => (value, parse_number(shift)?),
+        None => (expr, 0),
+    };
+
+    parse_number(value)
+        .or_else(|| defines.get(value).copied())?
+        .checked_shl(shift)
+}
+
+/// Get identifier of initializer (without type casts)
+fn ident_of(value: &[Tok<'_>]) -> String {
+    let tokens = || {
+        value
+            .iter()
+            .rev()
+            .filter(|(_, string)| string.is_none())
+            .map(|(slice, _)| *slice)
+    };
+
+    tokens()
+        .find(|slice| is_ident(slice) && !CAST_WORDS.contains(slice))
+        .or_else(|| tokens().find(|slice| slice.starts_with(|c: char| c.is_ascii_digit())))
+        .unwrap_or_default()
+        .into()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{MemTree, PathBuf, SourceData};
+
+    fn ids(src: &str, table: &str) -> Vec<String> {
+        DeviceTableData::from_source(src)[table]
+            .entries
+            .iter()
+            .map(|entry| entry.id.clone())
+            .collect()
+    }
+
+    #[test]
+    fn pci_table() {
+        let src = r#"
+#define FOO_DEVICE_ID 0x1234
+static const struct pci_device_id foo_ids[] = {
+	{ PCI_DEVICE(0x8086, FOO_DEVICE_ID) },
+	{ PCI_DEVICE_SUB(0x8086, 0x1235, 0x1028, PCI_ANY_ID) },
+	{ PCI_VDEVICE(INTEL, 0x1236), 0 },
+	{ PCI_VDEVICE_SUB(INTEL, 0x1237, 0x1028, 0x0001), 0 },
+	{ PCI_DEVICE_DATA(INTEL, FOO, 1) },
+	{ PCI_DEVICE_CLASS(0x010802, 0xffffff) },
+	{ PCI_DEVICE_CLASS(PCI_CLASS_STORAGE_SATA_AHCI, ~0) },
+	{ PCI_DEVICE_CLASS(0x010601, 0xffff00) },
+	{ .vendor = PCI_ANY_ID, .device = PCI_ANY_ID, .class = 0x0c0330, .class_mask = 0xff0000 },
+	{ PCI_DEVICE_CLASS(PCI_CLASS_STORAGE_IDE << 8, 0xffff00) },
+	{ 0, }
+};
+MODULE_DEVICE_TABLE(pci, foo_ids);
+"#;
+
+        let tables = DeviceTableData::from_source(src);
+        assert!(tables["foo_ids"].exported);
+        assert_eq!(tables["foo_ids"].bus, DeviceBus::Pci);
+        assert_eq!(
+            ids(src, "foo_ids"),
+            [
+                "8086:1234",
+                "8086:1235",
+                "PCI_VENDOR_ID_INTEL:1236",
+                "PCI_VENDOR_ID_INTEL:1237",
+                "PCI_VENDOR_ID_INTEL:PCI_DEVICE_ID_INTEL_FOO",
+                "*:*:010802",
+                "*:*:PCI_CLASS_STORAGE_SATA_AHCI",
+                "*:*:0106??",
+                "*:*:0c????",
+                "*:*:PCI_CLASS_STORAGE_IDE<<8&ffff00",
+            ]
+        );
+    }
+
+    #[test]
+    fn usb_table() {
+        let src = r#"
+static const struct usb_device_id foo_ids[] = {
+	{ USB_DEVICE(0x046d, 0xc52b) },
+	{ USB_DEVICE_VER(0x046d, 0xc52c, 0x0000, 0x0100) },
+	{ USB_DEVICE_INTERFACE_CLASS(0x046d, 0xc52d, 0xff) },
+	{ USB_DEVICE_INTERFACE_PROTOCOL(0x046d, 0xc52e, 0x01) },
+	{ USB_DEVICE_INTERFACE_NUMBER(0x046d, 0xc52f, 2) },
+	{ USB_DEVICE_AND_INTERFACE_INFO(0x046d, 0xc530, 3, 1, 2) },
+	{ USB_VENDOR_AND_INTERFACE_INFO(0x046d, 0xff, 0x01, 0x00) },
+	{ USB_DEVICE_INFO(9, 0, 1) },
+	{ USB_INTERFACE_INFO(USB_CLASS_MASS_STORAGE, 6, 0x50) },
+	{ USB_INTERFACE_CLASS(USB_CLASS_HID) },
+	{ .match_flags = USB_DEVICE_ID_MATCH_VENDOR, .idVendor = 0x0bda },
+	{ }
+};
+"#;
+
+        assert_eq!(
+            ids(src, "foo_ids"),
+            [
+                "046d:c52b",
+                "046d:c52c",
+                "046d:c52d:*:*:*:ff",
+                "046d:c52e:*:*:*:*:*:01",
+                "046d:c52f",
+                "046d:c530:*:*:*:03:01:02",
+                "046d:*:*:*:*:ff:01:00",
+                "*:*:09:00:01",
+                "*:*:*:*:*:USB_CLASS_MASS_STORAGE:06:50",
+                "*:*:*:*:*:USB_CLASS_HID",
+                "0bda:*",
+            ]
+        );
+    }
+
+    #[test]
+    fn named_tables() {
+        let src = r#"
+static const struct of_device_id foo_of_match[] = {
+	{ .compatible = "vendor,foo", .data = &foo_data },
+	{ .compatible = "vendor,bar" },
+	{ /* sentinel */ }
+};
+static const struct acpi_device_id foo_acpi_match[] = {
+	{ "FOO0001", 0 },
+	{ }
+};
+static const struct i2c_device_id foo_i2c_id[] = {
+	{ "foo", 0 },
+	{ }
+};
+static const struct spi_device_id foo_spi_id[] = {
+	{ .name = "foo-spi" },
+	{ }
+};
+static const struct platform_device_id foo_platform_ids[] = {
+	{ .name = "foo-platform", .driver_data = 1 },
+	{ }
+};
+"#;
+
+        let tables = DeviceTableData::from_source(src);
+        assert_eq!(tables["foo_of_match"].bus, DeviceBus::Of);
+        assert!(!tables["foo_of_match"].exported);
+        assert_eq!(ids(src, "foo_of_match"), ["vendor,foo", "vendor,bar"]);
+        assert_eq!(ids(src, "foo_acpi_match"), ["FOO0001"]);
+        assert_eq!(ids(src, "foo_i2c_id"), ["foo"]);
+        assert_eq!(ids(src, "foo_spi_id"), ["foo-spi"]);
+        assert_eq!(ids(src, "foo_platform_ids"), ["foo-platform"]);
+    }
+
+    #[tokio::test]
+    async fn resolve_ids() {
+        let tree = MemTree::new("linux");
+        tree.add_file(
+            "include/linux/pci_ids.h",
+            "#define PCI_CLASS_STORAGE_EXPRESS\t0x010802\n\
+             #define PCI_CLASS_STORAGE_SATA\t\t0x0106\n\
+             #define PCI_VENDOR_ID_INTEL\t\t0x8086\n",
+        );
+        tree.add_file(
+            "include/uapi/linux/usb/ch9.h",
+            "#define USB_CLASS_HID\t\t\t3\n",
+        );
+
+        let src = r#"
+static const struct pci_device_id nvme_id_table[] = {
+	{ PCI_DEVICE_CLASS(PCI_CLASS_STORAGE_EXPRESS, 0xffffff) },
+	{ PCI_DEVICE_CLASS(PCI_CLASS_STORAGE_SATA << 8, 0xffff00) },
+	{ PCI_VDEVICE(INTEL, 0x0953) },
+	{ 0, }
+};
+static const struct usb_device_id hid_ids[] = {
+	{ USB_INTERFACE_CLASS(USB_CLASS_HID) },
+	{ }
+};
+static const struct of_device_id foo_of_match[] = {
+	{ .compatible = "acme,foo" },
+	{ }
+};
+"#;
+
+        let mut metadata = MetaData::default();
+        metadata.sources.insert(
+            "drivers/foo.c".into(),
+            SourceData {
+                device_tables: DeviceTableData::from_source(src),
+                ..Default::default()
+            },
+        );
+        metadata
+            .resolve_device_ids(&FileMgr::from_tree(tree))
+            .await
+            .unwrap();
+
+        let tables = &metadata.source("drivers/foo.c").unwrap().device_tables;
+        let ids = |table: &str| {
+            tables[table]
+                .entries
+                .iter()
+                .map(|entry| entry.id.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            ids("nvme_id_table"),
+            ["*:*:010802", "*:*:0106??", "8086:0953"]
+        );
+        assert_eq!(ids("hid_ids"), ["*:*:*:*:*:03"]);
+
+        metadata.sync_with_sources();
+
+        let matches = |bus: DeviceBus, id: &str| metadata.find_devices(bus, id).count();
+        assert_eq!(matches(DeviceBus::Pci, "144d:a808:010802"), 1);
+        assert_eq!(matches(DeviceBus::Pci, "1022:7901:010601"), 1);
+        assert_eq!(matches(DeviceBus::Pci, "1022:7901:010400"), 0);
+        assert_eq!(matches(DeviceBus::Usb, "046d:c52b:00:00:00:03:01:02"), 1);
+        assert_eq!(
+            metadata.compat_str_sources("acme,foo"),
+            [&PathBuf::from("drivers/foo.c")].into()
+        );
+    }
+}
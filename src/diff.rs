@@ -0,0 +1,280 @@
+use crate::{MetaData, PathBuf, SourceData};
+use std::collections::BTreeSet as Set;
+
+/// Differences between two metadata (like for two kernel versions)
+#[derive(Debug, Default, Clone)]
+pub struct MetaDataDiff {
+    /// Added sources
+    pub added_sources: Vec<PathBuf>,
+
+    /// Removed sources
+    pub removed_sources: Vec<PathBuf>,
+
+    /// Moved or renamed sources (old path, new path)
+    pub moved_sources: Vec<(PathBuf, PathBuf)>,
+
+    /// Added compatible strings
+    pub added_compat_strs: Vec<String>,
+
+    /// Removed compatible strings
+    pub removed_compat_strs: Vec<String>,
+
+    /// Configuration options which sources was changed
+    pub config_opts: Vec<ConfigOptDiff>,
+
+    /// Added module parameters (like `arm_smmu.disable_bypass`)
+    pub added_params: Vec<String>,
+
+    /// Removed module parameters
+    pub removed_params: Vec<String>,
+
+    /// Module parameters which type was changed (name, old type, new type)
+    pub retyped_params: Vec<(String, String, String)>,
+
+    /// Sources which module license was changed (new path, old license, new license)
+    pub licenses: Vec<(PathBuf, String, String)>,
+}
+
+/// Changes of sources related to configuration option
+#[derive(Debug, Default, Clone)]
+pub struct ConfigOptDiff {
+    /// Configuration option name
+    pub name: String,
+
+    /// Sources which is not related to option anymore
+    pub removed_sources: Vec<PathBuf>,
+
+    /// Sources which is related to option now
+    pub added_sources: Vec<PathBuf>,
+}
+
+impl MetaDataDiff {
+    /// Check that there is no differences
+    pub fn is_empty(&self) -> bool {
+        self.added_sources.is_empty()
+            && self.removed_sources.is_empty()
+            && self.moved_sources.is_empty()
+            && self.added_compat_strs.is_empty()
+            && self.removed_compat_strs.is_empty()
+            && self.config_opts.is_empty()
+            && self.added_params.is_empty()
+            && self.removed_params.is_empty()
+            && self.retyped_params.is_empty()
+            && self.licenses.is_empty()
+    }
+}
+
+impl MetaData {
+    /// Get differences between this (old) and other (new) metadata
+    pub fn diff(&self, other: &Self) -> MetaDataDiff {
+        let mut diff = MetaDataDiff::default();
+
+        let mut removed = self
+            .sources
+            .keys()
+            .filter(|source| !other.sources.contains_key(*source))
+            .collect::<Vec<_>>();
+        let mut added = other
+            .sources
+            .keys()
+            .filter(|source| !self.sources.contains_key(*source))
+            .collect::<Vec<_>>();
+
+        // pair removed and added sources which looks like same source
+        removed.retain(|old| {
+            let found = added
+                .iter()
+                .position(|new| self.same_source(old, other, new));
+            if let Some(index) = found {
+                diff.moved_sources
+                    .push(((*old).clone(), added.remove(index).clone()));
+                false
+            } else {
+                true
+            }
+        });
+
+        diff.removed_sources = removed.into_iter().cloned().collect();
+        diff.added_sources = added.into_iter().cloned().collect();
+
+        diff.removed_compat_strs = self
+            .compat_strs
+            .keys()
+            .filter(|compat| !other.compat_strs.contains_key(*compat))
+            .cloned()
+            .collect();
+        diff.added_compat_strs = other
+            .compat_strs
+            .keys()
+            .filter(|compat| !self.compat_strs.contains_key(*compat))
+            .cloned()
+            .collect();
+
+        let config_opts = self
+            .config_opts
+            .keys()
+            .chain(other.config_opts.keys())
+            .collect::<Set<_>>();
+
+        for name in config_opts {
+            let empty = Set::default();
+            let old_sources = self
+                .config_opt(name)
+                .map(|data| &data.sources)
+                .unwrap_or(&empty);
+            let new_sources = other
+                .config_opt(name)
+                .map(|data| &data.sources)
+                .unwrap_or(&empty);
+
+            if old_sources != new_sources {
+                diff.config_opts.push(ConfigOptDiff {
+                    name: name.clone(),
+                    removed_sources: old_sources.difference(new_sources).cloned().collect(),
+                    added_sources: new_sources.difference(old_sources).cloned().collect(),
+                });
+            }
+        }
+
+        let old_params = self.module_params();
+        let new_params = other.module_params();
+
+        for (name, (_, param)) in &old_params {
+            match new_params.get(name) {
+                None => diff.removed_params.push(name.clone()),
+                Some((_, new_param)) if new_param.type_ != param.type_ => {
+                    diff.retyped_params.push((
+                        name.clone(),
+                        param.type_.clone(),
+                        new_param.type_.clone(),
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        diff.added_params = new_params
+            .keys()
+            .filter(|name| !old_params.contains_key(*name))
+            .cloned()
+            .collect();
+
+        let common = self
+            .sources
+            .keys()
+            .filter(|source| other.sources.contains_key(*source))
+            .map(|source| (source, source))
+            .chain(diff.moved_sources.iter().map(|(old, new)| (old, new)));
+
+        let mut licenses = Vec::default();
+
+        for (old, new) in common {
+            let old_license = license_of(&self.sources[old]);
+            let new_license = license_of(&other.sources[new]);
+
+            // sources without license declaration is not reported
+            if !old_license.is_empty() && !new_license.is_empty() && old_license != new_license {
+                licenses.push((new.clone(), old_license.into(), new_license.into()));
+            }
+        }
+
+        diff.licenses = licenses;
+
+        diff
+    }
+
+    /// Check that source of this metadata looks like source of other metadata
+    ///
+    /// Sources with same contents or same file name with same
+    /// compatible strings, exported symbols or module description considered as same.
+    fn same_source(&self, old: &PathBuf, other: &Self, new: &PathBuf) -> bool {
+        if let (Some(old_stamp), Some(new_stamp)) = (self.stamps.get(old), other.stamps.get(new)) {
+            if old_stamp.size == new_stamp.size && old_stamp.hash == new_stamp.hash {
+                return true;
+            }
+        }
+
+        if old.file_name() != new.file_name() {
+            return false;
+        }
+
+        let old_data = &self.sources[old];
+        let new_data = &other.sources[new];
+
+        (!old_data.compat_strs.is_empty() && old_data.compat_strs == new_data.compat_strs)
+            || (!old_data.exports.is_empty() && old_data.exports.keys().eq(new_data.exports.keys()))
+            || matches!(
+                (&old_data.module, &new_data.module),
+                (Some(old_module), Some(new_module))
+                    if !old_module.description.is_empty()
+                        && old_module.description == new_module.description
+            )
+    }
+}
+
+fn license_of(source_data: &SourceData) -> &str {
+    source_data
+        .module
+        .as_ref()
+        .map(|module| module.license.as_str())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ModuleData;
+
+    #[test]
+    fn compat_strs_and_licenses() {
+        let (mut old, mut new) = (MetaData::default(), MetaData::default());
+
+        for (metadata, compat_strs, license, bar) in [
+            (&mut old, &["acme,foo"][..], "GPL", "drivers/bar/bar.c"),
+            (
+                &mut new,
+                &["acme,foo", "acme,foo2"],
+                "Proprietary",
+                "drivers/misc/bar.c",
+            ),
+        ] {
+            let foo_c = metadata.source_mut("drivers/foo/foo.c");
+            foo_c
+                .compat_strs
+                .extend(compat_strs.iter().map(|compat| compat.to_string()));
+            foo_c.module = Some(ModuleData {
+                license: license.into(),
+                ..Default::default()
+            });
+            metadata
+                .source_mut(bar)
+                .exports
+                .insert("bar_register".into(), Default::default());
+            metadata.sync_with_sources();
+        }
+        new.source_mut("drivers/baz.c");
+
+        let diff = old.diff(&new);
+        assert_eq!(diff.added_compat_strs, ["acme,foo2"]);
+        assert!(diff.removed_compat_strs.is_empty());
+        assert_eq!(diff.added_sources, [PathBuf::from("drivers/baz.c")]);
+        assert!(diff.removed_sources.is_empty());
+        assert_eq!(
+            diff.moved_sources,
+            [(
+                PathBuf::from("drivers/bar/bar.c"),
+                "drivers/misc/bar.c".into()
+            )]
+        );
+        assert_eq!(
+            diff.licenses,
+            [(
+                PathBuf::from("drivers/foo/foo.c"),
+                "GPL".into(),
+                "Proprietary".into()
+            )]
+        );
+        assert!(!diff.is_empty());
+        assert!(old.diff(&old).is_empty());
+    }
+}
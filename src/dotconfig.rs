@@ -0,0 +1,182 @@
+use crate::{filemgr, KmodData, MetaData, Path, PathBuf, Result, SourceData};
+use std::collections::BTreeMap as Map;
+
+/// State of configuration option
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, educe::Educe)]
+#[educe(Default)]
+pub enum Tristate {
+    /// Disabled
+    #[educe(Default)]
+    No,
+
+    /// Enabled as loadable module
+    Module,
+
+    /// Enabled as built-in
+    Yes,
+}
+
+impl AsRef<str> for Tristate {
+    fn as_ref(&self) -> &str {
+        match self {
+            Self::No => "n",
+            Self::Module => "m",
+            Self::Yes => "y",
+        }
+    }
+}
+
+impl core::fmt::Display for Tristate {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str(self.as_ref())
+    }
+}
+
+/// Kernel configuration (.config or auto.conf)
+#[derive(Debug, Default, Clone)]
+pub struct DotConfig {
+    /// Values of configuration options (without `CONFIG_` prefix)
+    pub values: Map<String, String>,
+}
+
+impl DotConfig {
+    /// Parse kernel configuration
+    pub fn parse(text: impl AsRef<str>) -> Self {
+        let values = text
+            .as_ref()
+            .lines()
+            .map(str::trim)
+            // skip comments including "# CONFIG_FOO is not set"
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| line.split_once('='))
+            .filter_map(|(name, value)| {
+                let name = name.trim().strip_prefix("CONFIG_")?;
+                let value = value.trim();
+                let value = value
+                    .strip_prefix('"')
+                    .and_then(|value| value.strip_suffix('"'))
+                    .unwrap_or(value);
+                Some((name.into(), value.into()))
+            })
+            .collect();
+
+        Self { values }
+    }
+
+    /// Load kernel configuration from file
+    pub async fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+
+        log::debug!("Load kernel configuration from: {}", path.display());
+
+        let text = tokio::fs::read_to_string(path).await?;
+
+        Ok(Self::parse(text))
+    }
+
+    /// Load kernel configuration from path
+    ///
+    /// When path is a directory `include/config/auto.conf` or `.config` will be used.
+    pub async fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+
+        if filemgr::dir_exists(path).await {
+            for name in ["include/config/auto.conf", ".config"] {
+                let file_path = path.join(name);
+                if filemgr::file_exists(&file_path).await {
+                    return Self::from_file(file_path).await;
+                }
+            }
+            anyhow::bail!("Unable to find kernel configuration in {}", path.display());
+        }
+
+        Self::from_file(path).await
+    }
+
+    /// Get value of configuration option
+    pub fn value(&self, name: impl AsRef<str>) -> Option<&str> {
+        self.values.get(name.as_ref()).map(String::as_str)
+    }
+
+    /// Get state of configuration option
+    pub fn tristate(&self, name: impl AsRef<str>) -> Tristate {
+        match self.value(name) {
+            None | Some("n") | Some("") => Tristate::No,
+            Some("m") => Tristate::Module,
+            _ => Tristate::Yes,
+        }
+    }
+}
+
+impl SourceData {
+    /// Determine how source will be built using kernel configuration
+    pub fn tristate(&self, config: &DotConfig) -> Tristate {
+        self.condition.eval(config)
+    }
+}
+
+impl KmodData {
+    /// Determine how module will be built using kernel configuration
+    pub fn tristate(&self, config: &DotConfig) -> Tristate {
+        match (self.condition.eval(config), self.builtin) {
+            (Tristate::No, _) => Tristate::No,
+            (_, Some(true)) => Tristate::Yes,
+            (_, Some(false)) => Tristate::Module,
+            (tristate, None) => tristate,
+        }
+    }
+}
+
+impl MetaData {
+    /// Get sources which will be built using kernel configuration
+    ///
+    /// Sources linked into modules is built like the most enabled of its modules.
+    pub fn built_sources<'a>(
+        &'a self,
+        config: &'a DotConfig,
+    ) -> impl Iterator<Item = (&'a PathBuf, Tristate)> + 'a {
+        self.sources
+            .iter()
+            .map(|(source, source_data)| {
+                let tristate = source_data.tristate(config);
+                let tristate = self
+                    .kmods_of_source(source)
+                    .map(|(_, kmod_data)| kmod_data.tristate(config).min(tristate))
+                    .max()
+                    .unwrap_or(tristate);
+                (source, tristate)
+            })
+            .filter(|(_, tristate)| *tristate != Tristate::No)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{FileMgr, MemTree};
+
+    #[tokio::test]
+    async fn built_sources() {
+        let tree = MemTree::new("linux");
+        tree.add_file(
+            "Makefile",
+            "obj-m += foo.o\nobj-$(CONFIG_BAR) += bar.o\nobj-$(CONFIG_BAZ) += baz.o\n",
+        );
+        for source in ["foo.c", "bar.c", "baz.c"] {
+            tree.add_file(source, "");
+        }
+
+        let metadata = MetaData::from_kbuild_dir(&FileMgr::from_tree(tree), Path::new(""), None)
+            .await
+            .unwrap();
+        let config = DotConfig::parse("CONFIG_BAR=y\n# CONFIG_BAZ is not set\n");
+
+        assert_eq!(
+            metadata
+                .built_sources(&config)
+                .map(|(source, tristate)| (source.to_str().unwrap(), tristate))
+                .collect::<Vec<_>>(),
+            [("bar.c", Tristate::Yes), ("foo.c", Tristate::Module)]
+        );
+    }
+}
@@ -0,0 +1,227 @@
+use crate::{File, FileMgr, MetaData, Path, PathBuf, Result, SourceTree};
+use std::collections::BTreeSet as Set;
+
+/// Kernel tree with external modules directory placed under path prefix
+///
+/// Like `make -C <kernel> M=dir` external sources sees kernel headers
+/// while paths inside external directory gets prefixed.
+#[derive(Debug)]
+struct ExternalTree {
+    /// Kernel source tree
+    kernel: FileMgr,
+
+    /// External modules directory
+    external: FileMgr,
+
+    /// Path prefix of external modules directory
+    prefix: PathBuf,
+}
+
+impl ExternalTree {
+    /// Find tree which contains path with relative path inside it
+    fn resolve<'a>(&'a self, path: &'a Path) -> (&'a FileMgr, &'a Path) {
+        if let Ok(path) = path.strip_prefix(&self.prefix) {
+            (&self.external, path)
+        } else {
+            (&self.kernel, path)
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl SourceTree for ExternalTree {
+    fn base_path(&self) -> &PathBuf {
+        self.external.base_path()
+    }
+
+    async fn dir_exists(&self, path: &Path) -> Result<bool> {
+        let (filemgr, path) = self.resolve(path);
+        filemgr.dir_exists(path).await
+    }
+
+    async fn file_exists(&self, path: &Path) -> Result<bool> {
+        let (filemgr, path) = self.resolve(path);
+        filemgr.file_exists(path).await
+    }
+
+    async fn list_dir(&self, path: &Path) -> Result<Vec<PathBuf>> {
+        if let Ok(path) = path.strip_prefix(&self.prefix) {
+            Ok(self
+                .external
+                .list_dir(path)
+                .await?
+                .into_iter()
+                .map(|entry| self.prefix.join(entry))
+                .collect())
+        } else {
+            self.kernel.list_dir(path).await
+        }
+    }
+
+    async fn file_meta(&self, path: &Path) -> Result<(u64, u64)> {
+        let (filemgr, path) = self.resolve(path);
+        filemgr.file_meta(path).await
+    }
+
+    async fn open(&self, path: &Path) -> Result<File> {
+        let (filemgr, path) = self.resolve(path);
+        filemgr.open(path).await
+    }
+}
+
+impl MetaData {
+    /// Index external (out-of-tree) modules directory and merge it under path prefix
+    ///
+    /// Directory should contain `Kbuild` or `Makefile` like for `make M=dir`.
+    /// Includes of external sources is resolved against kernel tree too.
+    /// Previously merged data under same prefix is replaced.
+    pub async fn index_external(
+        &mut self,
+        kernel: &FileMgr,
+        filemgr: &FileMgr,
+        prefix: impl AsRef<Path>,
+    ) -> Result<()> {
+        let prefix = prefix.as_ref();
+
+        self.remove_external(prefix);
+
+        let tree = FileMgr::from_tree(ExternalTree {
+            kernel: kernel.clone(),
+            external: filemgr.clone(),
+            prefix: prefix.into(),
+        });
+
+        // kernel headers is reused instead of parsing
+        let previous = Self {
+            headers: self.headers.clone(),
+            stamps: self.stamps.clone(),
+            ..Default::default()
+        };
+
+        let mut external = Self::from_kbuild_dir(&tree, prefix, Some(previous)).await?;
+
+        // out-of-tree sources usually uses symbols exported by kernel
+        let mut exports = self.exported_symbols();
+        exports.extend(external.exported_symbols());
+
+        external
+            .scan_symbol_refs(&tree, &exports, &Set::default())
+            .await?;
+
+        // directory should be found again when index is updated from another place
+        let dir = tokio::fs::canonicalize(filemgr.base_path())
+            .await
+            .unwrap_or_else(|_| filemgr.base_path().clone());
+
+        self.merge_external(external, prefix);
+        self.externals.insert(prefix.into(), dir);
+
+        self.sync_with_sources();
+
+        Ok(())
+    }
+
+    /// Remove data of external modules merged under path prefix
+    pub fn remove_external(&mut self, prefix: impl AsRef<Path>) {
+        let prefix = prefix.as_ref();
+
+        self.sources.retain(|path, _| !path.starts_with(prefix));
+        self.headers.retain(|path, _| !path.starts_with(prefix));
+        self.kmods.retain(|path, _| !path.starts_with(prefix));
+        self.stamps.retain(|path, _| !path.starts_with(prefix));
+        self.makefiles.retain(|path, _| !path.starts_with(prefix));
+        self.externals.remove(prefix);
+    }
+
+    /// Add data of external modules under path prefix to this metadata
+    ///
+    /// Kernel headers used by external sources is already known so skipped.
+    fn merge_external(&mut self, external: Self, prefix: &Path) {
+        let inside = |path: &PathBuf| path.starts_with(prefix);
+
+        self.sources.extend(
+            external
+                .sources
+                .into_iter()
+                .filter(|(path, _)| inside(path)),
+        );
+        self.headers.extend(
+            external
+                .headers
+                .into_iter()
+                .filter(|(path, _)| inside(path)),
+        );
+        self.kmods
+            .extend(external.kmods.into_iter().filter(|(path, _)| inside(path)));
+        self.stamps
+            .extend(external.stamps.into_iter().filter(|(path, _)| inside(path)));
+        self.makefiles.extend(
+            external
+                .makefiles
+                .into_iter()
+                .filter(|(path, _)| inside(path)),
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{HeaderData, MemTree};
+
+    #[tokio::test]
+    async fn external_includes() {
+        let kernel = MemTree::new("linux");
+        kernel.add_file("include/linux/module.h", "#include <linux/init.h>\n");
+        kernel.add_file("include/linux/init.h", "");
+        kernel.add_file("drivers/foo.h", "");
+
+        let external = MemTree::new("modules");
+        external.add_file("Kbuild", "obj-m += foo.o\n");
+        external.add_file("foo.c", "#include <linux/module.h>\n#include \"foo.h\"\n");
+        external.add_file("foo.h", "");
+
+        let kernel = FileMgr::from_tree(kernel);
+        let mut metadata = MetaData::default();
+        metadata.headers.insert(
+            "include/linux/module.h".into(),
+            HeaderData {
+                includes: ["include/linux/init.h".into()].into(),
+                ..Default::default()
+            },
+        );
+
+        metadata
+            .index_external(&kernel, &FileMgr::from_tree(external), "extra/modules")
+            .await
+            .unwrap();
+
+        let source_data = metadata.source("extra/modules/foo.c").unwrap();
+        assert_eq!(
+            source_data.includes,
+            [
+                PathBuf::from("extra/modules/foo.h"),
+                "include/linux/module.h".into()
+            ]
+            .into()
+        );
+        assert!(metadata.kmod("extra/modules/foo.ko").is_some());
+        assert_eq!(
+            metadata
+                .stamps
+                .keys()
+                .map(|path| path.to_str().unwrap())
+                .collect::<Vec<_>>(),
+            [
+                "extra/modules/Kbuild",
+                "extra/modules/foo.c",
+                "extra/modules/foo.h"
+            ]
+        );
+
+        metadata.remove_external("extra/modules");
+        assert!(metadata.sources.is_empty());
+        assert!(metadata.stamps.is_empty());
+        assert!(metadata.header("extra/modules/foo.h").is_none());
+    }
+}
@@ -1,4 +1,7 @@
-use crate::{Path, PathBuf, Result};
+use crate::{DirTree, Path, PathBuf, Result, SourceTree};
+
+#[cfg(feature = "git")]
+use crate::GitTree;
 
 use std::{
     io,
@@ -10,18 +13,46 @@ use std::{
 };
 use tokio::{
     io::{AsyncRead, AsyncSeek, AsyncWrite, ReadBuf},
-    sync::{OwnedSemaphorePermit, Semaphore},
+    sync::OwnedSemaphorePermit,
 };
 
 /// File instance
-#[derive(Debug, educe::Educe)]
-#[educe(Deref, DerefMut)]
+#[derive(Debug)]
 #[pin_project::pin_project]
 pub struct File {
-    perm: OwnedSemaphorePermit,
-    #[educe(Deref, DerefMut)]
+    perm: Option<OwnedSemaphorePermit>,
     #[pin]
-    file: tokio::fs::File,
+    file: FileInner,
+}
+
+/// File backend
+#[derive(Debug)]
+#[pin_project::pin_project(project = FileInnerProj)]
+enum FileInner {
+    /// File in filesystem
+    Fs(#[pin] tokio::fs::File),
+    /// File contents in memory (read-only)
+    Mem(#[pin] io::Cursor<Arc<[u8]>>),
+}
+
+impl File {
+    /// Create file instance using opened file in filesystem
+    pub(crate) fn from_fs(file: tokio::fs::File, perm: OwnedSemaphorePermit) -> Self {
+        Self {
+            perm: Some(perm),
+            file: FileInner::Fs(file),
+        }
+    }
+
+    /// Create file instance using contents in memory
+    ///
+    /// Shared contents is not copied.
+    pub fn from_data(data: impl Into<Arc<[u8]>>) -> Self {
+        Self {
+            perm: None,
+            file: FileInner::Mem(io::Cursor::new(data.into())),
+        }
+    }
 }
 
 impl AsyncRead for File {
@@ -30,17 +61,26 @@ impl AsyncRead for File {
         cx: &mut Context<'_>,
         dst: &mut ReadBuf<'_>,
     ) -> Poll<io::Result<()>> {
-        self.project().file.poll_read(cx, dst)
+        match self.project().file.project() {
+            FileInnerProj::Fs(file) => file.poll_read(cx, dst),
+            FileInnerProj::Mem(file) => file.poll_read(cx, dst),
+        }
     }
 }
 
 impl AsyncSeek for File {
     fn start_seek(self: Pin<&mut Self>, pos: SeekFrom) -> io::Result<()> {
-        self.project().file.start_seek(pos)
+        match self.project().file.project() {
+            FileInnerProj::Fs(file) => file.start_seek(pos),
+            FileInnerProj::Mem(file) => file.start_seek(pos),
+        }
     }
 
     fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
-        self.project().file.poll_complete(cx)
+        match self.project().file.project() {
+            FileInnerProj::Fs(file) => file.poll_complete(cx),
+            FileInnerProj::Mem(file) => file.poll_complete(cx),
+        }
     }
 }
 
@@ -50,60 +90,45 @@ impl AsyncWrite for File {
         cx: &mut Context<'_>,
         src: &[u8],
     ) -> Poll<io::Result<usize>> {
-        self.project().file.poll_write(cx, src)
+        match self.project().file.project() {
+            FileInnerProj::Fs(file) => file.poll_write(cx, src),
+            FileInnerProj::Mem(_) => Poll::Ready(Err(read_only())),
+        }
     }
 
     fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
-        self.project().file.poll_flush(cx)
+        match self.project().file.project() {
+            FileInnerProj::Fs(file) => file.poll_flush(cx),
+            FileInnerProj::Mem(_) => Poll::Ready(Ok(())),
+        }
     }
 
     fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
-        self.project().file.poll_flush(cx)
-    }
-}
-
-#[cfg(unix)]
-impl std::os::unix::io::AsRawFd for File {
-    fn as_raw_fd(&self) -> std::os::unix::io::RawFd {
-        self.file.as_raw_fd()
+        self.poll_flush(cx)
     }
 }
 
-#[cfg(windows)]
-impl std::os::windows::io::AsRawHandle for File {
-    fn as_raw_handle(&self) -> std::os::windows::io::RawHandle {
-        self.file.as_raw_handle()
-    }
+fn read_only() -> io::Error {
+    io::Error::new(io::ErrorKind::PermissionDenied, "File is read-only")
 }
 
 /// File manager
 #[derive(Debug, Clone)]
 pub struct FileMgr {
-    /// Base directory
-    dir: Arc<PathBuf>,
-
-    /// Open files semaphore
-    sem: Arc<Semaphore>,
+    /// Source tree
+    tree: Arc<dyn SourceTree>,
 }
 
 impl AsRef<Path> for FileMgr {
     fn as_ref(&self) -> &Path {
-        &self.dir
+        self.tree.base_path()
     }
 }
 
 impl FileMgr {
     /// Create file manager instance using specified base directory
     pub async fn new(dir: impl Into<PathBuf>) -> Result<Self> {
-        let (soft, hard) = rlimit::Resource::NOFILE.get()?;
-        let max_open_files = soft.min(hard) - 10;
-
-        log::debug!("Max open files: {}", max_open_files);
-
-        let this = Self {
-            dir: Arc::new(dir.into()),
-            sem: Arc::new(Semaphore::new(max_open_files as _)),
-        };
+        let this = Self::from_tree(DirTree::new(dir)?);
 
         if !this.dir_exists(".").await? {
             anyhow::bail!(
@@ -115,9 +140,33 @@ impl FileMgr {
         Ok(this)
     }
 
+    /// Create file manager instance using tree of git revision in local repository
+    #[cfg(feature = "git")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "git")))]
+    pub async fn from_git(repo: impl Into<PathBuf>, rev: impl AsRef<str>) -> Result<Self> {
+        let repo = repo.into();
+        let rev = rev.as_ref().to_owned();
+
+        Ok(Self::from_tree(
+            tokio::task::spawn_blocking(move || GitTree::open(repo, rev)).await??,
+        ))
+    }
+
+    /// Create file manager instance using custom source tree
+    pub fn from_tree(tree: impl SourceTree + 'static) -> Self {
+        Self {
+            tree: Arc::new(tree),
+        }
+    }
+
+    /// Get hash of tree contents when it is known (like git tree hash)
+    pub fn tree_id(&self) -> Option<String> {
+        self.tree.tree_id()
+    }
+
     /// Get base directory path
     pub fn base_path(&self) -> &PathBuf {
-        &self.dir
+        self.tree.base_path()
     }
 
     /// Get full path to files in base directory using relative path
@@ -126,33 +175,40 @@ impl FileMgr {
         if path.is_absolute() {
             anyhow::bail!("Path should be relative: {}", path.display());
         }
-        Ok(self.dir.join(path))
+        Ok(self.base_path().join(path))
     }
 
     /// Check directory existing in base directory using relative path
     pub async fn dir_exists(&self, path: impl AsRef<Path>) -> Result<bool> {
-        Ok(dir_exists(self.full_path(path)?).await)
+        self.tree.dir_exists(path.as_ref()).await
     }
 
     /// Check file existing in base directory using relative path
     pub async fn file_exists(&self, path: impl AsRef<Path>) -> Result<bool> {
-        Ok(file_exists(self.full_path(path)?).await)
+        self.tree.file_exists(path.as_ref()).await
+    }
+
+    /// List directory entries in base directory using relative path
+    pub async fn list_dir(&self, path: impl AsRef<Path>) -> Result<Vec<PathBuf>> {
+        self.tree.list_dir(path.as_ref()).await
+    }
+
+    /// Get modification time and size of file using relative path
+    ///
+    /// Modification time is nanoseconds since UNIX epoch for filesystem trees,
+    /// other trees may use any value which changes when file changes (like blob id).
+    pub async fn file_meta(&self, path: impl AsRef<Path>) -> Result<(u64, u64)> {
+        self.tree.file_meta(path.as_ref()).await
     }
 
     /// Open file in base directory using relative path
     pub async fn open(&self, path: impl AsRef<Path>) -> Result<File> {
-        let perm = Semaphore::acquire_owned(self.sem.clone()).await?;
-        let file = tokio::fs::File::open(self.full_path(path)?).await?;
-
-        Ok(File { perm, file })
+        self.tree.open(path.as_ref()).await
     }
 
     /// Create file in base directory using relative path
     pub async fn create(&self, path: impl AsRef<Path>) -> Result<File> {
-        let perm = Semaphore::acquire_owned(self.sem.clone()).await?;
-        let file = tokio::fs::File::create(self.full_path(path)?).await?;
-
-        Ok(File { perm, file })
+        self.tree.create(path.as_ref()).await
     }
 }
 
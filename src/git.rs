@@ -0,0 +1,156 @@
+use crate::{File, Path, PathBuf, Result, SourceTree};
+use std::collections::BTreeMap as Map;
+
+/// Tree of git revision in local repository
+///
+/// Files are read directly from object database so checkout is not needed.
+#[derive(Debug)]
+pub struct GitTree {
+    /// Repository path
+    path: PathBuf,
+
+    /// Thread-safe repository handle
+    repo: gix::ThreadSafeRepository,
+
+    /// Tree object id
+    tree: gix::ObjectId,
+
+    /// Tree entries by relative path
+    entries: Map<PathBuf, GitEntry>,
+}
+
+/// Entry of git tree
+#[derive(Debug, Clone)]
+enum GitEntry {
+    /// Directory with paths of entries
+    Dir(Vec<PathBuf>),
+
+    /// File with blob id
+    File(gix::ObjectId),
+}
+
+impl GitTree {
+    /// Open tree of revision (commit, tag or branch) in repository
+    ///
+    /// Whole tree is traversed once so paths lookup does not touch object database.
+    pub fn open(path: impl Into<PathBuf>, rev: impl AsRef<str>) -> Result<Self> {
+        let path = path.into();
+        let rev = rev.as_ref();
+        let repo = gix::open(&path)?;
+        let tree = repo.rev_parse_single(rev)?.object()?.peel_to_tree()?.id;
+
+        log::debug!("Git revision {} has tree {}", rev, tree);
+
+        let mut entries = Map::default();
+        add_tree(&repo, tree, PathBuf::default(), &mut entries)?;
+
+        log::debug!("Git tree {} has {} entries", tree, entries.len());
+
+        Ok(Self {
+            path,
+            repo: repo.into_sync(),
+            tree,
+            entries,
+        })
+    }
+
+    /// Find tree entry by relative path
+    fn entry(&self, path: &Path) -> Option<&GitEntry> {
+        self.entries.get(path.strip_prefix(".").unwrap_or(path))
+    }
+
+    /// Find blob id by relative path
+    fn blob(&self, path: &Path) -> Result<gix::ObjectId> {
+        match self.entry(path) {
+            Some(GitEntry::File(id)) => Ok(*id),
+            _ => anyhow::bail!("File not found: {}", path.display()),
+        }
+    }
+}
+
+/// Add entries of tree object recursively
+fn add_tree(
+    repo: &gix::Repository,
+    id: gix::ObjectId,
+    path: PathBuf,
+    entries: &mut Map<PathBuf, GitEntry>,
+) -> Result<()> {
+    let tree = repo.find_object(id)?.try_into_tree()?;
+    let mut names = Vec::default();
+
+    for entry in tree.decode()?.entries {
+        let name = path.join(entry.filename.to_string());
+
+        if entry.mode.is_tree() {
+            add_tree(repo, entry.oid.into(), name.clone(), entries)?;
+        } else if entry.mode.is_blob() {
+            entries.insert(name.clone(), GitEntry::File(entry.oid.into()));
+        } else {
+            // symlinks and submodules is not supported
+            continue;
+        }
+
+        names.push(name);
+    }
+
+    entries.insert(path, GitEntry::Dir(names));
+
+    Ok(())
+}
+
+#[async_trait::async_trait]
+impl SourceTree for GitTree {
+    fn base_path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    fn tree_id(&self) -> Option<String> {
+        Some(self.tree.to_string())
+    }
+
+    async fn dir_exists(&self, path: &Path) -> Result<bool> {
+        Ok(matches!(self.entry(path), Some(GitEntry::Dir(_))))
+    }
+
+    async fn file_exists(&self, path: &Path) -> Result<bool> {
+        Ok(matches!(self.entry(path), Some(GitEntry::File(_))))
+    }
+
+    async fn list_dir(&self, path: &Path) -> Result<Vec<PathBuf>> {
+        match self.entry(path) {
+            Some(GitEntry::Dir(names)) => Ok(names.clone()),
+            _ => anyhow::bail!("Directory not found: {}", path.display()),
+        }
+    }
+
+    /// First bytes of blob id is used as modification time so unchanged blobs is detected
+    /// without reading contents.
+    async fn file_meta(&self, path: &Path) -> Result<(u64, u64)> {
+        let id = self.blob(path)?;
+        let repo = self.repo.clone();
+        let size = tokio::task::spawn_blocking(move || {
+            repo.to_thread_local()
+                .find_header(id)
+                .map(|header| header.size())
+        })
+        .await??;
+        let mut mtime = [0u8; 8];
+
+        mtime.copy_from_slice(&id.as_bytes()[..8]);
+
+        Ok((u64::from_be_bytes(mtime), size))
+    }
+
+    async fn open(&self, path: &Path) -> Result<File> {
+        let id = self.blob(path)?;
+        let repo = self.repo.clone();
+        let data = tokio::task::spawn_blocking(move || {
+            repo.to_thread_local()
+                .find_object(id)
+                .map(|object| object.detach().data)
+        })
+        .await??;
+
+        Ok(File::from_data(data))
+    }
+}
@@ -240,6 +240,14 @@ impl MetaData {
             DataCoding::Cbor => ciborium::de::from_reader(data)?,
         };
 
+        if data.version != Self::FORMAT_VERSION {
+            anyhow::bail!(
+                "Unsupported index format version {} (expected {}), index should be recreated",
+                data.version,
+                Self::FORMAT_VERSION
+            );
+        }
+
         data.sync_with_sources();
 
         Ok(data)
@@ -1,4 +1,7 @@
-use crate::{FileMgr, MakeFile, MakeStmt, MetaData, Path, PathBuf, Result, SourceData};
+use crate::{
+    Condition, File, FileMgr, FileStamp, HeaderData, MakeFile, MakeStmt, MetaData, Path, PathBuf,
+    Result, SourceData, SourceLang,
+};
 
 use std::{
     collections::{BTreeSet as Set, VecDeque},
@@ -8,6 +11,7 @@ use std::{
     },
 };
 use tokio::{
+    io::AsyncReadExt,
     spawn,
     sync::{
         mpsc::{channel, Sender},
@@ -17,7 +21,7 @@ use tokio::{
 
 #[derive(Debug)]
 struct ConditionsData {
-    condition: String,
+    condition: Condition,
     conditions: Conditions,
 }
 
@@ -26,50 +30,111 @@ pub struct Conditions {
     inner: Arc<Option<ConditionsData>>,
 }
 
-impl<Rhs: AsRef<str>> core::ops::AddAssign<Rhs> for Conditions {
-    fn add_assign(&mut self, new_condition: Rhs) {
+impl core::ops::AddAssign<Condition> for Conditions {
+    fn add_assign(&mut self, new_condition: Condition) {
         *self = self.clone() + new_condition;
     }
 }
 
-impl<Rhs: AsRef<str>> core::ops::Add<Rhs> for Conditions {
+impl core::ops::Add<Condition> for Conditions {
     type Output = Self;
 
-    fn add(self, new_condition: Rhs) -> Self {
-        let new_condition = new_condition.as_ref();
+    fn add(self, new_condition: Condition) -> Self {
         Self {
             inner: Arc::new(Some(ConditionsData {
-                condition: new_condition.into(),
+                condition: new_condition,
                 conditions: self,
             })),
         }
     }
 }
 
-impl<'a> From<&'a Conditions> for Set<String> {
+impl From<&Conditions> for Condition {
     fn from(mut conditions: &Conditions) -> Self {
-        let mut set = Set::default();
+        let mut list = Vec::default();
         while let Some(data) = &*conditions.inner {
-            set.insert(data.condition.clone());
+            list.push(data.condition.clone());
             conditions = &data.conditions;
         }
-        set
+        // outer conditions goes first
+        Condition::all(list.into_iter().rev())
     }
 }
 
 impl MetaData {
     /// Create metadata by indexing kbuild files and sources
     pub async fn from_kbuild(filemgr: &FileMgr) -> Result<Self> {
-        let state = State::new(filemgr.clone());
+        Self::from_kbuild_incremental(filemgr, None).await
+    }
+
+    /// Create metadata by indexing kbuild files and sources reusing previous metadata
+    ///
+    /// Data of sources and headers which was not changed since previous indexing is reused
+    /// instead of parsing. Statements of unchanged kbuild files is reused too while kbuild
+    /// files of parent directories is unchanged.
+    pub async fn from_kbuild_incremental(
+        filemgr: &FileMgr,
+        previous: Option<MetaData>,
+    ) -> Result<Self> {
+        // external modules is merged after indexing so their exports is not compared
+        let prev_exports = previous.as_ref().map(|previous| {
+            previous
+                .sources
+                .iter()
+                .filter(|(path, _)| {
+                    !previous
+                        .externals
+                        .keys()
+                        .any(|prefix| path.starts_with(prefix))
+                })
+                .flat_map(|(_, source_data)| source_data.exports.keys().cloned())
+                .collect::<Set<_>>()
+        });
+
+        let state = State::new(filemgr.clone(), previous);
 
         state.process().await?;
 
-        let mut result = state.result()?;
+        let (mut result, reused) = state.result()?;
+
+        result.index_kconfig(filemgr).await?;
+
+        result.resolve_device_ids(filemgr).await?;
+
+        result.index_devicetree(filemgr).await?;
+
+        result.index_bindings(filemgr).await?;
+
+        result.index_maintainers(filemgr).await?;
+
+        // symbol references of reused sources is actual while exported symbols is same
+        let unchanged = if prev_exports == Some(result.exported_symbols()) {
+            reused
+        } else {
+            Set::default()
+        };
+
+        result.index_symbol_refs(filemgr, &unchanged).await?;
 
         result.sync_with_sources();
 
         Ok(result)
     }
+
+    /// Create metadata by indexing kbuild files and sources of directory only
+    ///
+    /// Data of unchanged headers is reused from previous metadata.
+    pub(crate) async fn from_kbuild_dir(
+        filemgr: &FileMgr,
+        dir: &Path,
+        previous: Option<MetaData>,
+    ) -> Result<Self> {
+        let state = State::new(filemgr.clone(), previous).with_subdir(dir);
+
+        state.process().await?;
+
+        Ok(state.result()?.0)
+    }
 }
 
 #[derive(Debug)]
@@ -78,8 +143,14 @@ struct StateData {
     filemgr: FileMgr,
     /** Processed kbuild files */
     donekbuild: RwLock<Set<PathBuf>>,
+    /** Processed header files */
+    doneheaders: RwLock<Set<PathBuf>>,
     /** Result metadata */
     metadata: RwLock<MetaData>,
+    /** Previous metadata */
+    previous: Option<MetaData>,
+    /** Reused sources */
+    reused: RwLock<Set<PathBuf>>,
 }
 
 #[derive(Debug)]
@@ -98,18 +169,24 @@ struct State {
     path: Arc<PathBuf>,
     /** Current conditions set */
     conditions: Conditions,
+    /** Kbuild file of parent directory was changed */
+    changed: bool,
 }
 
 impl State {
-    fn new(filemgr: FileMgr) -> Self {
+    fn new(filemgr: FileMgr, previous: Option<MetaData>) -> Self {
         Self {
             shared: Arc::new(StateData {
                 filemgr,
                 donekbuild: RwLock::new(Set::default()),
+                doneheaders: RwLock::new(Set::default()),
                 metadata: RwLock::new(MetaData::default()),
+                previous,
+                reused: RwLock::new(Set::default()),
             }),
             path: Arc::new(PathBuf::default()),
             conditions: Conditions::default(),
+            changed: false,
         }
     }
 
@@ -123,13 +200,15 @@ impl State {
         state
     }
 
-    fn add_condition(&mut self, condition: impl AsRef<str>) {
-        self.conditions += condition;
+    fn add_condition(&mut self, condition: Condition) {
+        if !condition.is_true() {
+            self.conditions += condition;
+        }
     }
 
     async fn add_source(&self, path: impl AsRef<Path>, data: impl Into<SourceData>) {
         let mut data = data.into();
-        data.add_config_opts(&self.conditions);
+        data.add_condition(Condition::from(&self.conditions));
 
         self.metadata
             .write()
@@ -138,32 +217,169 @@ impl State {
             .insert(path.as_ref().into(), data);
     }
 
-    async fn add_object(&self, name: impl AsRef<Path>) -> Result<()> {
+    /// Add object and returns path to source when found
+    async fn add_object(&self, name: impl AsRef<Path>) -> Result<Option<PathBuf>> {
         let path = self.path.join(name);
-        for extension in ["c", "S"] {
+        for extension in SourceLang::EXTENSIONS {
             let source_path = path.with_extension(extension);
             if self.filemgr.file_exists(&source_path).await? {
-                if extension == "c" {
-                    match SourceData::from_source(&self.filemgr, &source_path).await {
-                        Ok(source_data) => self.add_source(&source_path, source_data).await,
-                        Err(error) => {
-                            log::warn!("Unable to find compats for: {:?} due to: {}", path, error);
-                        }
+                let (stamp, data) = self.read_changed(&source_path).await?;
+                let previous = self
+                    .previous
+                    .as_ref()
+                    .filter(|_| data.is_none())
+                    .and_then(|previous| previous.source(&source_path))
+                    .cloned();
+                let source_data = if let Some(mut source_data) = previous {
+                    // build conditions will be added again
+                    source_data.condition = Condition::default();
+                    source_data.config_opts.clear();
+                    self.reused.write().await.insert(source_path.clone());
+                    Ok(source_data)
+                } else if let Some(data) = data {
+                    match String::from_utf8(data) {
+                        Ok(src) => SourceData::parse(&self.filemgr, &source_path, &src).await,
+                        Err(error) => Err(error.into()),
+                    }
+                } else {
+                    SourceData::from_source(&self.filemgr, &source_path).await
+                };
+                match source_data {
+                    Ok(source_data) => {
+                        let includes = source_data.includes.clone();
+                        self.add_source(&source_path, source_data).await;
+                        self.add_stamp(&source_path, stamp).await;
+                        self.add_headers(includes).await?;
+                    }
+                    Err(error) => {
+                        log::warn!("Unable to parse source: {:?} due to: {}", path, error);
                     }
                 }
 
-                return Ok(());
+                return Ok(Some(source_path));
             }
         }
         log::warn!("Unable to find source for: {:?}", path);
+        Ok(None)
+    }
+
+    /// Add source to module declared by `obj-*` variable
+    async fn add_kmod_source(&self, name: impl AsRef<Path>, source: impl Into<PathBuf>) {
+        let path = self.path.join(name).with_extension("ko");
+
+        if let Some(kmod_data) = self.metadata.write().await.kmods.get_mut(&path) {
+            kmod_data.add_source(source);
+        }
+    }
+
+    /// Add module with build condition
+    async fn add_kmod(&self, name: impl AsRef<Path>, builtin: Option<bool>) {
+        let path = self.path.join(name).with_extension("ko");
+        let mut metadata = self.metadata.write().await;
+        let kmod_data = metadata.kmod_mut(path);
+
+        kmod_data.condition = Condition::from(&self.conditions);
+        kmod_data.builtin = builtin;
+    }
+
+    /// Get modification stamp of file with contents when file was changed since previous indexing
+    ///
+    /// Contents is read only when modification time or size differs from previous stamp,
+    /// same data is used to calculate hash and to parse file. Unchanged file has no contents.
+    async fn read_changed(&self, path: &Path) -> Result<(FileStamp, Option<Vec<u8>>)> {
+        let prev_stamp = self
+            .previous
+            .as_ref()
+            .and_then(|previous| previous.stamps.get(path));
+        let (mtime, size) = self.filemgr.file_meta(path).await?;
+
+        if let Some(prev_stamp) = prev_stamp {
+            if prev_stamp.same_meta(mtime, size) {
+                return Ok((*prev_stamp, None));
+            }
+        }
+
+        let mut file = self.filemgr.open(path).await?;
+        let mut data = Vec::default();
+
+        file.read_to_end(&mut data).await?;
+
+        let stamp = FileStamp {
+            mtime,
+            size,
+            hash: FileStamp::hash_of(&data),
+        };
+        let unchanged = prev_stamp
+            .map(|prev_stamp| prev_stamp.size == stamp.size && prev_stamp.hash == stamp.hash)
+            .unwrap_or(false);
+
+        Ok((stamp, (!unchanged).then_some(data)))
+    }
+
+    async fn add_stamp(&self, path: &Path, stamp: FileStamp) {
+        self.metadata
+            .write()
+            .await
+            .stamps
+            .insert(path.into(), stamp);
+    }
+
+    async fn add_headers(&self, includes: impl IntoIterator<Item = PathBuf>) -> Result<()> {
+        let mut queue = includes.into_iter().collect::<Vec<_>>();
+
+        while let Some(path) = queue.pop() {
+            if !self.doneheaders.write().await.insert(path.clone()) {
+                continue;
+            }
+
+            // skip unresolved includes
+            if !self.filemgr.file_exists(&path).await? {
+                continue;
+            }
+
+            let (stamp, data) = self.read_changed(&path).await?;
+            let previous = self
+                .previous
+                .as_ref()
+                .filter(|_| data.is_none())
+                .and_then(|previous| previous.header(&path))
+                .cloned();
+            let header_data = if let Some(header_data) = previous {
+                Ok(header_data)
+            } else if let Some(data) = data {
+                match String::from_utf8(data) {
+                    Ok(src) => HeaderData::parse(&self.filemgr, &path, &src).await,
+                    Err(error) => Err(error.into()),
+                }
+            } else {
+                HeaderData::from_header(&self.filemgr, &path).await
+            };
+
+            match header_data {
+                Ok(header_data) => {
+                    queue.extend(header_data.includes.iter().cloned());
+                    self.add_stamp(&path, stamp).await;
+                    self.metadata
+                        .write()
+                        .await
+                        .headers
+                        .insert(path, header_data);
+                }
+                Err(error) => {
+                    log::warn!("Unable to parse header: {:?} due to: {}", path, error);
+                }
+            }
+        }
+
         Ok(())
     }
 
-    fn result(self) -> Result<MetaData> {
-        Ok(Arc::try_unwrap(self.shared)
-            .or_else(|_| anyhow::bail!("Unable to unwrap data"))?
-            .metadata
-            .into_inner())
+    /// Get result metadata with set of reused sources
+    fn result(self) -> Result<(MetaData, Set<PathBuf>)> {
+        let shared =
+            Arc::try_unwrap(self.shared).or_else(|_| anyhow::bail!("Unable to unwrap data"))?;
+
+        Ok((shared.metadata.into_inner(), shared.reused.into_inner()))
     }
 
     async fn process(&self) -> Result<()> {
@@ -191,10 +407,10 @@ impl State {
                     //log::trace!("add task: {}", count);
 
                     spawn(async move {
-                        if let Err(err) = tx
-                            .send(StateOp::Done(state.process_dir(tx.clone()).await))
-                            .await
-                        {
+                        let result = state.process_dir(tx.clone()).await;
+                        // shared data should be released before task is done
+                        drop(state);
+                        if let Err(err) = tx.send(StateOp::Done(result)).await {
                             eprintln!("Unable to finalize task due to: {}", err);
                         }
                     });
@@ -227,29 +443,96 @@ impl State {
         Ok(())
     }
 
+    /// Leave current branch of conditional directive to enter the next one
+    ///
+    /// The conditions of all previous branches will be negated.
+    fn else_branch(stack: &mut VecDeque<(State, Vec<Condition>)>) -> (State, Vec<Condition>) {
+        let branches = if stack.len() > 1 {
+            stack.pop_back().unwrap().1
+        } else {
+            Vec::default()
+        };
+
+        let mut state = stack.back().unwrap().0.clone();
+
+        for condition in &branches {
+            // conditions which is not related to configuration options cannot be negated
+            if !condition.is_true() {
+                state.add_condition(!condition.clone());
+            }
+        }
+
+        (state, branches)
+    }
+
+    /// Get statements of kbuild file reusing previous ones when file and parents was not changed
+    async fn makefile_stmts(&self, path: &Path) -> Result<(Vec<MakeStmt>, bool)> {
+        let (stamp, data) = self.read_changed(path).await?;
+        let previous = self
+            .previous
+            .as_ref()
+            .filter(|_| data.is_none() && !self.changed)
+            .and_then(|previous| previous.makefiles.get(path))
+            .cloned();
+        let reused = previous.is_some();
+        let stmts = if let Some(stmts) = previous {
+            stmts
+        } else {
+            log::debug!("parse kbuild file: {:?}", path);
+            let file = if let Some(data) = data {
+                File::from_data(data)
+            } else {
+                self.filemgr.open(path).await?
+            };
+            MakeFile::from_file(file).stmts().await?
+        };
+
+        let mut metadata = self.metadata.write().await;
+        metadata.stamps.insert(path.into(), stamp);
+        metadata.makefiles.insert(path.into(), stmts.clone());
+
+        Ok((stmts, reused))
+    }
+
     async fn process_makefile(&self, path: impl AsRef<Path>, tx: &Sender<StateOp>) -> Result<()> {
-        let mut makefile = MakeFile::parse(&self.filemgr, path).await?;
+        let (stmts, reused) = self.makefile_stmts(path.as_ref()).await?;
+        let mut state = self.clone();
+
+        // kbuild files of subdirectories is parsed again when this one was changed
+        state.changed = !reused;
 
+        // (state, conditions of previous branches)
         let mut stack = VecDeque::default();
-        stack.push_back(self.clone());
+        stack.push_back((state, Vec::default()));
 
-        while let Some(stmt) = makefile.next_stmt().await? {
+        for stmt in stmts {
             log::trace!("Make statement: {:?}", stmt);
             match stmt {
                 MakeStmt::Var {
-                    conditions,
+                    prefix,
+                    condition,
                     elements,
-                    ..
+                    builtin,
                 } => {
-                    let mut state = stack.back().unwrap().clone();
-                    for condition in conditions {
-                        state.add_condition(condition);
-                    }
+                    let mut state = stack.back().unwrap().0.clone();
+                    state.add_condition(condition);
+                    // members of composite object (like `<module>-y := a.o b.o`)
+                    let composite =
+                        !["obj", "lib", "subdir", "core", "drivers"].contains(&prefix.as_str());
                     for element in &elements {
                         let name = Path::new(element);
                         if let Some(extension) = name.extension() {
                             if extension == "o" {
-                                state.add_object(name).await?;
+                                if prefix == "obj" {
+                                    state.add_kmod(name, builtin).await;
+                                }
+                                if let Some(source) = state.add_object(name).await? {
+                                    if composite {
+                                        state.add_kmod_source(&prefix, source).await;
+                                    } else if prefix == "obj" && builtin != Some(true) {
+                                        state.add_kmod_source(name, source).await;
+                                    }
+                                }
                             }
                         } else {
                             let state = state.clone();
@@ -257,27 +540,25 @@ impl State {
                         }
                     }
                 }
-                MakeStmt::If { conditions } => {
-                    let mut state = stack.back().unwrap().clone();
-                    for condition in conditions {
-                        state.add_condition(condition);
-                    }
-                    stack.push_back(state);
+                MakeStmt::If { condition } => {
+                    let mut state = stack.back().unwrap().0.clone();
+                    state.add_condition(condition.clone());
+                    stack.push_back((state, vec![condition]));
                 }
                 MakeStmt::EndIf => {
                     if stack.len() > 1 {
                         stack.pop_back();
                     }
                 }
-                MakeStmt::ElseIf { conditions } => {
-                    if stack.len() > 1 {
-                        stack.pop_back();
-                    }
-                    let mut state = stack.back().unwrap().clone();
-                    for condition in conditions {
-                        state.add_condition(condition);
-                    }
-                    stack.push_back(state);
+                MakeStmt::ElseIf { condition } => {
+                    let (mut state, mut branches) = Self::else_branch(&mut stack);
+                    state.add_condition(condition.clone());
+                    branches.push(condition);
+                    stack.push_back((state, branches));
+                }
+                MakeStmt::Else => {
+                    let branch = Self::else_branch(&mut stack);
+                    stack.push_back(branch);
                 }
             }
         }
@@ -285,3 +566,222 @@ impl State {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::MemTree;
+
+    /// Index kbuild files of tree with Makefile and empty sources
+    async fn index(makefile: &str, sources: &[&str]) -> MetaData {
+        let tree = MemTree::new("linux");
+
+        tree.add_file("Makefile", makefile);
+        for source in sources {
+            tree.add_file(*source, "");
+        }
+
+        MetaData::from_kbuild_dir(&FileMgr::from_tree(tree), Path::new(""), None)
+            .await
+            .unwrap()
+    }
+
+    fn condition(metadata: &MetaData, source: &str) -> String {
+        metadata.source(source).unwrap().condition.to_string()
+    }
+
+    #[tokio::test]
+    async fn nested_conditionals() {
+        let metadata = index(
+            r#"
+obj-$(CONFIG_A) += a.o
+ifdef CONFIG_B
+obj-y += b.o
+ifndef CONFIG_C
+obj-y += c.o
+else
+obj-y += d.o
+endif
+else ifeq ($(CONFIG_E),y)
+obj-y += e.o
+else
+obj-y += f.o
+endif
+obj-y += g.o
+"#,
+            &["a.c", "b.c", "c.c", "d.c", "e.c", "f.c", "g.c"],
+        )
+        .await;
+
+        assert_eq!(condition(&metadata, "a.c"), "A");
+        assert_eq!(condition(&metadata, "b.c"), "B");
+        assert_eq!(condition(&metadata, "c.c"), "B && !C");
+        assert_eq!(condition(&metadata, "d.c"), "B && C");
+        assert_eq!(condition(&metadata, "e.c"), "!B && E=y");
+        assert_eq!(condition(&metadata, "f.c"), "!B && !E=y");
+        assert_eq!(condition(&metadata, "g.c"), "y");
+    }
+
+    #[tokio::test]
+    async fn negated_conditionals() {
+        // like arch/arm64/kernel/Makefile and drivers/acpi/Makefile
+        let metadata = index(
+            r#"
+ifneq ($(CONFIG_ACPI),)
+obj-y += acpi.o
+endif
+ifeq ($(CONFIG_NUMA),)
+obj-y += numa_stub.o
+else
+obj-y += numa.o
+endif
+ifneq ($(CONFIG_HIBERNATION),y)
+obj-y += no_hibernate.o
+else ifneq ($(CONFIG_SUSPEND),y)
+obj-y += no_suspend.o
+endif
+"#,
+            &[
+                "acpi.c",
+                "numa_stub.c",
+                "numa.c",
+                "no_hibernate.c",
+                "no_suspend.c",
+            ],
+        )
+        .await;
+
+        assert_eq!(condition(&metadata, "acpi.c"), "ACPI");
+        assert_eq!(condition(&metadata, "numa_stub.c"), "!NUMA");
+        assert_eq!(condition(&metadata, "numa.c"), "NUMA");
+        assert_eq!(condition(&metadata, "no_hibernate.c"), "!HIBERNATION=y");
+        assert_eq!(
+            condition(&metadata, "no_suspend.c"),
+            "HIBERNATION=y && !SUSPEND=y"
+        );
+    }
+
+    #[tokio::test]
+    async fn composite_objects() {
+        // like drivers/iommu/arm/arm-smmu/Makefile
+        let metadata = index(
+            r#"
+obj-$(CONFIG_ARM_SMMU) += arm_smmu.o
+arm_smmu-objs += arm-smmu.o arm-smmu-impl.o
+arm_smmu-$(CONFIG_ARM_SMMU_QCOM) += arm-smmu-qcom.o
+"#,
+            &["arm-smmu.c", "arm-smmu-impl.c", "arm-smmu-qcom.c"],
+        )
+        .await;
+
+        assert_eq!(condition(&metadata, "arm-smmu.c"), "ARM_SMMU");
+        assert_eq!(condition(&metadata, "arm-smmu-impl.c"), "ARM_SMMU");
+        assert_eq!(
+            condition(&metadata, "arm-smmu-qcom.c"),
+            "ARM_SMMU && ARM_SMMU_QCOM"
+        );
+
+        let (path, kmod_data) = metadata.kmod("arm_smmu").unwrap();
+        assert_eq!(path, Path::new("arm_smmu.ko"));
+        assert_eq!(kmod_data.sources.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn subdirs() {
+        let tree = MemTree::new("linux");
+
+        tree.add_file("Makefile", "obj-$(CONFIG_NET) += net/\n");
+        tree.add_file("net/Makefile", "ifdef CONFIG_INET\nobj-y += ipv4/\nendif\n");
+        tree.add_file("net/ipv4/Makefile", "obj-m += tcp.o\n");
+        tree.add_file("net/ipv4/tcp.c", "");
+
+        let metadata = MetaData::from_kbuild_dir(&FileMgr::from_tree(tree), Path::new(""), None)
+            .await
+            .unwrap();
+
+        assert_eq!(condition(&metadata, "net/ipv4/tcp.c"), "NET && INET");
+    }
+
+    /// Index kbuild files of tree reusing previous metadata
+    async fn reindex(files: &[(&str, &str)], previous: Option<MetaData>) -> MetaData {
+        let tree = MemTree::new("linux");
+
+        for (path, data) in files {
+            tree.add_file(*path, *data);
+        }
+
+        let state = State::new(FileMgr::from_tree(tree), previous);
+        state.process().await.unwrap();
+        state.result().unwrap().0
+    }
+
+    #[tokio::test]
+    async fn reused_makefiles() {
+        let mut files = vec![
+            ("Makefile", "obj-$(CONFIG_NET) += net/\n"),
+            ("net/Makefile", "obj-$(CONFIG_INET) += ipv4/\n"),
+            ("net/ipv4/Makefile", "obj-$(CONFIG_TCP) += tcp.o\n"),
+            ("net/ipv4/tcp.c", ""),
+        ];
+        let mut metadata = reindex(&files, None).await;
+
+        assert_eq!(condition(&metadata, "net/ipv4/tcp.c"), "NET && INET && TCP");
+
+        // statements of unchanged kbuild file is not parsed again
+        let stmts = MakeFile::from_file(File::from_data(b"obj-$(CONFIG_UDP) += tcp.o\n".to_vec()))
+            .stmts()
+            .await
+            .unwrap();
+        metadata
+            .makefiles
+            .insert("net/ipv4/Makefile".into(), stmts.clone());
+
+        let mut metadata = reindex(&files, Some(metadata)).await;
+
+        assert_eq!(condition(&metadata, "net/ipv4/tcp.c"), "NET && INET && UDP");
+
+        // changed kbuild file invalidates its subdirectories
+        metadata.makefiles.insert("net/ipv4/Makefile".into(), stmts);
+        files[1].1 = "obj-$(CONFIG_IPV4) += ipv4/\n";
+
+        let metadata = reindex(&files, Some(metadata)).await;
+
+        assert_eq!(condition(&metadata, "net/ipv4/tcp.c"), "NET && IPV4 && TCP");
+    }
+
+    #[tokio::test]
+    async fn source_includes() {
+        let tree = MemTree::new("linux");
+
+        tree.add_file("Makefile", "obj-y += drivers/\n");
+        tree.add_file("drivers/Makefile", "obj-$(CONFIG_FOO) += foo/\n");
+        tree.add_file("drivers/foo/Makefile", "obj-m += foo.o\n");
+        tree.add_file(
+            "drivers/foo/foo.c",
+            "#include <linux/module.h>\n#include \"foo.h\"\n",
+        );
+        tree.add_file("drivers/foo/foo.h", "");
+        tree.add_file("include/linux/module.h", "#include <linux/export.h>\n");
+        tree.add_file("include/linux/export.h", "");
+
+        let metadata = MetaData::from_kbuild_dir(&FileMgr::from_tree(tree), Path::new(""), None)
+            .await
+            .unwrap();
+
+        let foo_c = metadata.source("drivers/foo/foo.c").unwrap();
+        assert_eq!(
+            foo_c.includes,
+            [
+                PathBuf::from("drivers/foo/foo.h"),
+                "include/linux/module.h".into()
+            ]
+            .into()
+        );
+        assert_eq!(
+            metadata.header("include/linux/module.h").unwrap().includes,
+            ["include/linux/export.h".into()].into()
+        );
+        assert_eq!(condition(&metadata, "drivers/foo/foo.c"), "FOO");
+        assert!(metadata.kmod("foo").is_some());
+    }
+}
@@ -0,0 +1,780 @@
+use crate::{Condition, ConfigOptType, File, FileMgr, MetaData, Path, PathBuf, Result, SourceLoc};
+use std::{collections::BTreeSet as Set, iter::Peekable};
+use tokio::io::{AsyncBufReadExt, BufReader, Lines};
+
+pub struct KconfigFile {
+    path: PathBuf,
+    lines: Lines<BufReader<File>>,
+    line: u32,
+    // line which should be processed again
+    pending: Option<(u32, String)>,
+}
+
+#[derive(Debug)]
+pub enum KconfigStmt {
+    Source {
+        path: String,
+        relative: bool,
+        optional: bool,
+    },
+    Config {
+        name: String,
+    },
+    Type {
+        type_: ConfigOptType,
+        prompt: Option<String>,
+    },
+    Prompt {
+        prompt: String,
+    },
+    Help {
+        text: String,
+    },
+    DependsOn {
+        expr: String,
+    },
+    Select {
+        symbol: String,
+        condition: Condition,
+    },
+    Imply {
+        symbol: String,
+        condition: Condition,
+    },
+    Menu,
+    EndMenu,
+    Choice,
+    EndChoice,
+    If {
+        expr: String,
+    },
+    EndIf,
+    Comment,
+    MainMenu,
+    // any other entry attribute
+    Attr,
+}
+
+impl KconfigFile {
+    pub async fn parse(filemgr: &FileMgr, path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+
+        log::debug!("parse kconfig file: {:?}", path);
+
+        let file = filemgr.open(path).await?;
+        let lines = BufReader::new(file).lines();
+
+        Ok(Self {
+            path: path.into(),
+            lines,
+            line: 0,
+            pending: None,
+        })
+    }
+
+    async fn next_line(&mut self) -> Result<Option<(u32, String)>> {
+        if let Some(line) = self.pending.take() {
+            return Ok(Some(line));
+        }
+
+        Ok(self.lines.next_line().await?.map(|line| {
+            self.line += 1;
+            (self.line, line)
+        }))
+    }
+
+    async fn read_help(&mut self) -> Result<String> {
+        let mut text = String::new();
+        let mut indent = None;
+        let mut empty_lines = 0;
+
+        while let Some((line_no, line)) = self.next_line().await? {
+            if line.trim().is_empty() {
+                empty_lines += 1;
+                continue;
+            }
+
+            let line_indent = indent_width(&line);
+
+            let indent = match indent {
+                Some(indent) if line_indent >= indent => indent,
+                None if line_indent > 0 => *indent.insert(line_indent),
+                _ => {
+                    // end of help text
+                    self.pending = Some((line_no, line));
+                    break;
+                }
+            };
+
+            if !text.is_empty() {
+                for _ in 0..=empty_lines {
+                    text.push('\n');
+                }
+            }
+            empty_lines = 0;
+
+            for _ in indent..line_indent {
+                text.push(' ');
+            }
+            text.push_str(line.trim());
+        }
+
+        Ok(text)
+    }
+
+    pub async fn next_stmt(&mut self) -> Result<Option<(u32, KconfigStmt)>> {
+        while let Some((line_no, mut line)) = self.next_line().await? {
+            while let Some(head) = line.strip_suffix('\\') {
+                let mut head = head.to_string();
+                if let Some((_, tail)) = self.next_line().await? {
+                    head.push_str(&tail);
+                }
+                line = head;
+            }
+
+            let line = strip_comment(&line).trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            let (keyword, rest) = line
+                .split_once(char::is_whitespace)
+                .map(|(keyword, rest)| (keyword, rest.trim()))
+                .unwrap_or((line, ""));
+
+            let stmt = match keyword {
+                "source" | "rsource" | "osource" | "orsource" => {
+                    if let Some((path, _)) = parse_string(rest) {
+                        KconfigStmt::Source {
+                            path,
+                            relative: keyword.ends_with("rsource"),
+                            optional: keyword.starts_with('o'),
+                        }
+                    } else {
+                        log::warn!("Invalid source at {}:{}", self.path.display(), line_no);
+                        continue;
+                    }
+                }
+                "config" | "menuconfig" => {
+                    if let Some(name) = rest.split_whitespace().next() {
+                        KconfigStmt::Config { name: name.into() }
+                    } else {
+                        log::warn!("Invalid config at {}:{}", self.path.display(), line_no);
+                        continue;
+                    }
+                }
+                "bool" | "boolean" | "tristate" | "int" | "hex" | "string" => KconfigStmt::Type {
+                    type_: keyword.parse()?,
+                    prompt: parse_string(rest).map(|(prompt, _)| prompt),
+                },
+                "def_bool" | "def_tristate" => KconfigStmt::Type {
+                    type_: keyword.parse()?,
+                    prompt: None,
+                },
+                "prompt" => {
+                    if let Some((prompt, _)) = parse_string(rest) {
+                        KconfigStmt::Prompt { prompt }
+                    } else {
+                        KconfigStmt::Attr
+                    }
+                }
+                "help" | "---help---" => KconfigStmt::Help {
+                    text: self.read_help().await?,
+                },
+                "depends" => {
+                    if let Some(expr) = rest.strip_prefix("on") {
+                        KconfigStmt::DependsOn {
+                            expr: expr.trim().into(),
+                        }
+                    } else {
+                        KconfigStmt::Attr
+                    }
+                }
+                "select" | "imply" => {
+                    if let Some((symbol, rest)) = rest
+                        .split_once(char::is_whitespace)
+                        .or_else(|| Some((rest, "")).filter(|_| !rest.is_empty()))
+                    {
+                        let symbol = symbol.into();
+                        // select FOO if BAR
+                        let condition = rest
+                            .trim()
+                            .strip_prefix("if")
+                            .map(parse_expr)
+                            .unwrap_or_default();
+                        if keyword == "select" {
+                            KconfigStmt::Select { symbol, condition }
+                        } else {
+                            KconfigStmt::Imply { symbol, condition }
+                        }
+                    } else {
+                        KconfigStmt::Attr
+                    }
+                }
+                "menu" => KconfigStmt::Menu,
+                "endmenu" => KconfigStmt::EndMenu,
+                "choice" => KconfigStmt::Choice,
+                "endchoice" => KconfigStmt::EndChoice,
+                "if" => KconfigStmt::If { expr: rest.into() },
+                "endif" => KconfigStmt::EndIf,
+                "comment" => KconfigStmt::Comment,
+                "mainmenu" => KconfigStmt::MainMenu,
+                _ => KconfigStmt::Attr,
+            };
+
+            return Ok(Some((line_no, stmt)));
+        }
+
+        Ok(None)
+    }
+}
+
+impl MetaData {
+    /// Index configuration options defined in Kconfig files
+    pub(crate) async fn index_kconfig(&mut self, filemgr: &FileMgr) -> Result<()> {
+        let root = Path::new("Kconfig");
+
+        if !filemgr.file_exists(root).await? {
+            log::warn!("Missing root Kconfig file");
+            return Ok(());
+        }
+
+        let mut donefiles = Set::default();
+        let mut files = vec![KconfigFile::parse(filemgr, root).await?];
+        donefiles.insert(root.to_path_buf());
+
+        // currently processed entry
+        let mut entry = Entry::None;
+        // dependencies from enclosing menus, choices and if-blocks
+        let mut depends: Vec<Condition> = Vec::default();
+
+        while let Some(file) = files.last_mut() {
+            let (line, stmt) = if let Some(stmt) = file.next_stmt().await? {
+                stmt
+            } else {
+                files.pop();
+                self.replace_entry(&mut entry, Entry::None);
+                continue;
+            };
+
+            log::trace!("Kconfig statement: {:?}", stmt);
+
+            match stmt {
+                KconfigStmt::Source {
+                    path,
+                    relative,
+                    optional,
+                } => {
+                    self.replace_entry(&mut entry, Entry::None);
+
+                    let path = if relative {
+                        let dir = file.path.parent().unwrap_or_else(|| Path::new(""));
+                        dir.join(path).to_string_lossy().into_owned()
+                    } else {
+                        path
+                    };
+
+                    for path in expand_path(filemgr, &path).await? {
+                        if donefiles.contains(&path) {
+                            continue;
+                        }
+
+                        if filemgr.file_exists(&path).await? {
+                            files.push(KconfigFile::parse(filemgr, &path).await?);
+                            donefiles.insert(path);
+                        } else if !optional {
+                            log::warn!("Unable to find Kconfig file: {:?}", path);
+                        }
+                    }
+                }
+                KconfigStmt::Config { name } => {
+                    let location = SourceLoc {
+                        file: file.path.clone(),
+                        line,
+                    };
+                    self.config_opt_mut(&name).locations.push(location);
+                    let depends_on = Condition::all(depends.iter().cloned());
+                    self.replace_entry(&mut entry, Entry::Config(name, depends_on));
+                }
+                KconfigStmt::Type { type_, prompt } => {
+                    if let Entry::Config(name, _) = &entry {
+                        let data = self.config_opt_mut(name);
+                        if data.type_.is_none() {
+                            data.type_ = Some(type_);
+                        }
+                        if let Some(prompt) = prompt {
+                            if data.prompt.is_empty() {
+                                data.prompt = prompt;
+                            }
+                        }
+                    }
+                }
+                KconfigStmt::Prompt { prompt } => {
+                    if let Entry::Config(name, _) = &entry {
+                        let data = self.config_opt_mut(name);
+                        if data.prompt.is_empty() {
+                            data.prompt = prompt;
+                        }
+                    }
+                }
+                KconfigStmt::Help { text } => {
+                    if let Entry::Config(name, _) = &entry {
+                        let data = self.config_opt_mut(name);
+                        if data.help.is_empty() {
+                            data.help = text;
+                        }
+                    }
+                }
+                KconfigStmt::DependsOn { expr } => match &mut entry {
+                    Entry::Config(_, depends_on) => {
+                        *depends_on &= parse_expr(&expr);
+                    }
+                    Entry::Block => {
+                        if let Some(condition) = depends.last_mut() {
+                            *condition &= parse_expr(&expr);
+                        }
+                    }
+                    Entry::None => {}
+                },
+                KconfigStmt::Select { symbol, condition } => {
+                    if let Entry::Config(name, _) = &entry {
+                        self.config_opt_mut(name).selects.insert(symbol, condition);
+                    }
+                }
+                KconfigStmt::Imply { symbol, condition } => {
+                    if let Entry::Config(name, _) = &entry {
+                        self.config_opt_mut(name).implies.insert(symbol, condition);
+                    }
+                }
+                KconfigStmt::Attr => {}
+                KconfigStmt::Menu | KconfigStmt::Choice => {
+                    depends.push(Condition::default());
+                    self.replace_entry(&mut entry, Entry::Block);
+                }
+                KconfigStmt::If { expr } => {
+                    depends.push(parse_expr(&expr));
+                    self.replace_entry(&mut entry, Entry::None);
+                }
+                KconfigStmt::EndMenu | KconfigStmt::EndChoice | KconfigStmt::EndIf => {
+                    depends.pop();
+                    self.replace_entry(&mut entry, Entry::None);
+                }
+                KconfigStmt::Comment | KconfigStmt::MainMenu => {
+                    self.replace_entry(&mut entry, Entry::None);
+                }
+            }
+        }
+
+        self.replace_entry(&mut entry, Entry::None);
+
+        Ok(())
+    }
+
+    /// Finish currently processed entry and start new one
+    fn replace_entry(&mut self, entry: &mut Entry, new_entry: Entry) {
+        if let Entry::Config(name, depends_on) = core::mem::replace(entry, new_entry) {
+            let data = self.config_opt_mut(name);
+            // dependencies of multiple definitions is alternatives
+            data.depends_on = if data.locations.len() > 1 && !data.depends_on.is_true() {
+                if depends_on.is_true() {
+                    depends_on
+                } else {
+                    Condition::any([core::mem::take(&mut data.depends_on), depends_on])
+                }
+            } else {
+                depends_on
+            };
+        }
+    }
+}
+
+enum Entry {
+    None,
+    // configuration option with dependencies of definition
+    Config(String, Condition),
+    // menu or choice
+    Block,
+}
+
+/// Token of Kconfig expression
+#[derive(Debug, Clone, PartialEq)]
+enum ExprToken<'a> {
+    /// Symbol or constant (like `FOO`, `y` or `"string"`)
+    Symbol(&'a str),
+    /// Macro invocation (like `$(cc-option,-mno-sse)`)
+    Macro,
+    /// Operator or parenthesis
+    Op(&'a str),
+}
+
+/// Split Kconfig expression to tokens
+fn expr_tokens(expr: &str) -> Vec<ExprToken<'_>> {
+    let mut tokens = Vec::default();
+    let mut chars = expr.char_indices().peekable();
+
+    while let Some((start, chr)) = chars.next() {
+        match chr {
+            '"' | '\'' => {
+                let mut escape = false;
+                let mut end = expr.len();
+                for (idx, c) in chars.by_ref() {
+                    if escape {
+                        escape = false;
+                    } else if c == '\\' {
+                        escape = true;
+                    } else if c == chr {
+                        end = idx;
+                        break;
+                    }
+                }
+                tokens.push(ExprToken::Symbol(&expr[start + 1..end]));
+            }
+            '$' if matches!(chars.peek(), Some((_, '('))) => {
+                let mut depth = 0usize;
+                for (_, c) in chars.by_ref() {
+                    match c {
+                        '(' => depth += 1,
+                        ')' if depth > 1 => depth -= 1,
+                        ')' => break,
+                        _ => {}
+                    }
+                }
+                tokens.push(ExprToken::Macro);
+            }
+            '&' | '|' | '!' | '=' | '<' | '>' => {
+                let mut end = start + 1;
+                if let Some((idx, next)) = chars.peek() {
+                    if matches!(
+                        (chr, next),
+                        ('&', '&') | ('|', '|') | ('!' | '<' | '>', '=')
+                    ) {
+                        end = idx + 1;
+                        chars.next();
+                    }
+                }
+                tokens.push(ExprToken::Op(&expr[start..end]));
+            }
+            '(' | ')' => tokens.push(ExprToken::Op(&expr[start..start + 1])),
+            c if c.is_alphanumeric() || c == '_' || c == '-' => {
+                let mut end = start + c.len_utf8();
+                while let Some((idx, c)) = chars.peek() {
+                    if c.is_alphanumeric() || *c == '_' || *c == '-' {
+                        end = idx + c.len_utf8();
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(ExprToken::Symbol(&expr[start..end]));
+            }
+            _ => {}
+        }
+    }
+
+    tokens
+}
+
+/// Parse Kconfig expression (like `depends on A && (B || !C)`) into condition
+///
+/// Macros and numeric comparisons (`<`, `>=` and so on) cannot be evaluated so treated as met.
+/// Constant `n` is never met while `m` is kept to be evaluated as module.
+pub(crate) fn parse_expr(expr: &str) -> Condition {
+    let tokens = expr_tokens(expr);
+    let mut tokens = tokens.iter().cloned().peekable();
+
+    parse_or(&mut tokens)
+}
+
+fn parse_or<'a, I: Iterator<Item = ExprToken<'a>>>(tokens: &mut Peekable<I>) -> Condition {
+    let mut conditions = vec![parse_and(tokens)];
+
+    while tokens.next_if_eq(&ExprToken::Op("||")).is_some() {
+        conditions.push(parse_and(tokens));
+    }
+
+    // any of alternatives is always met when one of them is always met
+    if conditions.iter().any(Condition::is_true) {
+        Condition::default()
+    } else {
+        Condition::any(conditions)
+    }
+}
+
+fn parse_and<'a, I: Iterator<Item = ExprToken<'a>>>(tokens: &mut Peekable<I>) -> Condition {
+    let mut condition = parse_not(tokens);
+
+    while tokens.next_if_eq(&ExprToken::Op("&&")).is_some() {
+        condition &= parse_not(tokens);
+    }
+
+    condition
+}
+
+fn parse_not<'a, I: Iterator<Item = ExprToken<'a>>>(tokens: &mut Peekable<I>) -> Condition {
+    if tokens.next_if_eq(&ExprToken::Op("!")).is_some() {
+        let condition = parse_not(tokens);
+        // negation of unknown condition is unknown too
+        if condition.is_true() {
+            condition
+        } else {
+            !condition
+        }
+    } else {
+        parse_primary(tokens)
+    }
+}
+
+fn parse_primary<'a, I: Iterator<Item = ExprToken<'a>>>(tokens: &mut Peekable<I>) -> Condition {
+    match tokens.next() {
+        Some(ExprToken::Op("(")) => {
+            let condition = parse_or(tokens);
+            tokens.next_if_eq(&ExprToken::Op(")"));
+            condition
+        }
+        Some(ExprToken::Symbol(symbol)) => {
+            let op = tokens.next_if(|token| {
+                matches!(token, ExprToken::Op("=" | "!=" | "<" | ">" | "<=" | ">="))
+            });
+            let value = if op.is_some() {
+                match tokens.next() {
+                    Some(ExprToken::Symbol(value)) => Some(value),
+                    _ => None,
+                }
+            } else {
+                None
+            };
+
+            match (op, value) {
+                (None, _) if symbol == "n" => Condition::any([]),
+                // kept as is to be evaluated as module
+                (None, _) if symbol == "m" => Condition::Symbol(symbol.into()),
+                (None, _) if is_constant(symbol) => Condition::default(),
+                (None, _) => Condition::Symbol(symbol.into()),
+                (Some(ExprToken::Op("=")), Some(value)) if !is_constant(symbol) => {
+                    Condition::Equal(symbol.into(), value.into())
+                }
+                (Some(ExprToken::Op("!=")), Some(value)) if !is_constant(symbol) => {
+                    !Condition::Equal(symbol.into(), value.into())
+                }
+                _ => Condition::default(),
+            }
+        }
+        _ => Condition::default(),
+    }
+}
+
+fn is_constant(symbol: &str) -> bool {
+    matches!(symbol, "y" | "m" | "n")
+        || symbol.chars().all(|c| c.is_ascii_digit())
+        || symbol
+            .strip_prefix("0x")
+            .map(|hex| hex.chars().all(|c| c.is_ascii_hexdigit()))
+            .unwrap_or(false)
+}
+
+/// Expand variables in path of sourced Kconfig file
+async fn expand_path(filemgr: &FileMgr, path: &str) -> Result<Vec<PathBuf>> {
+    const ARCH_VARS: [&str; 3] = ["$(SRCARCH)", "$(ARCH)", "$(HEADER_ARCH)"];
+
+    if let Some(var) = ARCH_VARS.into_iter().find(|var| path.contains(var)) {
+        // source Kconfig files of all architectures
+        let mut paths = Vec::default();
+
+        for arch in filemgr.list_dir("arch").await? {
+            if filemgr.dir_exists(&arch).await? {
+                if let Some(arch) = arch.file_name().and_then(|name| name.to_str()) {
+                    paths.push(path.replace(var, arch).into());
+                }
+            }
+        }
+
+        Ok(paths)
+    } else if path.contains("$(") {
+        log::warn!("Unable to expand Kconfig path: {:?}", path);
+        Ok(Vec::default())
+    } else {
+        Ok(vec![path.into()])
+    }
+}
+
+fn indent_width(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| c.is_whitespace())
+        .fold(0, |width, c| {
+            if c == '\t' {
+                (width & !7) + 8
+            } else {
+                width + 1
+            }
+        })
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut escape = false;
+
+    for (idx, chr) in line.char_indices() {
+        if escape {
+            escape = false;
+            continue;
+        }
+        match (quote, chr) {
+            (Some(_), '\\') => escape = true,
+            (Some(q), c) if c == q => quote = None,
+            (None, '"' | '\'') => quote = Some(chr),
+            (None, '#') => return &line[..idx],
+            _ => {}
+        }
+    }
+
+    line
+}
+
+fn parse_string(s: &str) -> Option<(String, &str)> {
+    let s = s.trim_start();
+    let quote = s.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let mut string = String::new();
+    let mut chars = s.char_indices().skip(1);
+
+    while let Some((idx, chr)) = chars.next() {
+        if chr == '\\' {
+            if let Some((_, chr)) = chars.next() {
+                string.push(chr);
+            }
+        } else if chr == quote {
+            return Some((string, &s[idx + 1..]));
+        } else {
+            string.push(chr);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{DotConfig, MemTree, Tristate};
+
+    fn sym(name: &str) -> Condition {
+        Condition::Symbol(name.into())
+    }
+
+    #[test]
+    fn expr_conjunction() {
+        let condition = parse_expr("A && B=y && C != n");
+
+        assert_eq!(
+            condition,
+            Condition::all([
+                sym("A"),
+                Condition::Equal("B".into(), "y".into()),
+                !Condition::Equal("C".into(), "n".into()),
+            ])
+        );
+        assert_eq!(
+            condition.required_symbols(),
+            ["A", "B"].into_iter().collect()
+        );
+    }
+
+    #[test]
+    fn expr_alternatives_is_not_required() {
+        let condition = parse_expr("OF || COMPILE_TEST");
+
+        assert_eq!(condition, Condition::any([sym("OF"), sym("COMPILE_TEST")]));
+        assert!(condition.required_symbols().is_empty());
+    }
+
+    #[test]
+    fn expr_negation_is_not_required() {
+        let condition = parse_expr("!B && (C || D) && E");
+
+        assert_eq!(
+            condition,
+            Condition::all([!sym("B"), Condition::any([sym("C"), sym("D")]), sym("E"),])
+        );
+        assert_eq!(condition.required_symbols(), ["E"].into_iter().collect());
+    }
+
+    #[test]
+    fn expr_unknown_is_met() {
+        assert!(parse_expr("$(cc-option,-mno-sse)").is_true());
+        assert!(parse_expr("y").is_true());
+        assert!(parse_expr("NR_CPUS > 1 || A").is_true());
+        assert_eq!(parse_expr("64BIT && $(as-instr,nop)"), sym("64BIT"));
+    }
+
+    #[test]
+    fn expr_tristate_constants() {
+        assert!(parse_expr("n").is_false());
+        assert!(parse_expr("A && n").is_false());
+        assert!(parse_expr("!n").is_true());
+        assert_eq!(parse_expr("n || A"), sym("A"));
+
+        let condition = parse_expr("A && m");
+        assert_eq!(condition, Condition::all([sym("A"), sym("m")]));
+        assert_eq!(condition.required_symbols(), ["A"].into_iter().collect());
+        assert_eq!(
+            condition.eval(&DotConfig::parse("CONFIG_A=y\n")),
+            Tristate::Module
+        );
+    }
+
+    #[tokio::test]
+    async fn depends_on_n() {
+        let tree = MemTree::new("linux");
+
+        tree.add_file(
+            "Kconfig",
+            "config FOO\n\tbool \"Foo\"\n\tdepends on n\n\nconfig BAR\n\ttristate \"Bar\"\n\tdepends on m\n",
+        );
+
+        let mut metadata = MetaData::default();
+        metadata
+            .index_kconfig(&FileMgr::from_tree(tree))
+            .await
+            .unwrap();
+
+        let foo = &metadata.config_opt("FOO").unwrap().depends_on;
+        assert!(foo.is_false());
+        assert_eq!(foo.to_string(), "n");
+        assert_eq!(
+            metadata
+                .config_opt("BAR")
+                .unwrap()
+                .depends_on
+                .eval(&DotConfig::default()),
+            Tristate::Module
+        );
+    }
+
+    #[tokio::test]
+    async fn index_config_opts() {
+        let tree = MemTree::new("linux");
+
+        tree.add_file(
+            "Kconfig",
+            "source \"arch/$(SRCARCH)/Kconfig\"\nsource \"drivers/foo/Kconfig\"\n",
+        );
+        tree.add_file("arch/arm64/Kconfig", "config ARM64\n\tdef_bool y\n");
+        tree.add_file(
+            "drivers/foo/Kconfig",
+            "config FOO\n\ttristate \"Foo driver\"\n\tdepends on OF && !BAR\n\tselect REGMAP if I2C\n",
+        );
+
+        let mut metadata = MetaData::default();
+        metadata
+            .index_kconfig(&FileMgr::from_tree(tree))
+            .await
+            .unwrap();
+
+        assert!(metadata.config_opt("ARM64").unwrap().is_defined());
+
+        let foo = metadata.config_opt("FOO").unwrap();
+        assert_eq!(foo.locations[0].file, Path::new("drivers/foo/Kconfig"));
+        assert_eq!(foo.depends_on.to_string(), "OF && !BAR");
+        assert_eq!(foo.selects["REGMAP"].to_string(), "I2C");
+    }
+}
@@ -1,11 +1,28 @@
 #![doc = include_str!("../README.md")]
 #![cfg_attr(feature = "doc-cfg", feature(doc_cfg))]
 
+#[cfg(feature = "archive")]
+mod archive;
+mod binding;
+mod condition;
+mod devicetree;
+mod devtable;
+mod diff;
+mod dotconfig;
+mod external;
 mod filemgr;
+#[cfg(feature = "git")]
+mod git;
 mod kbuild;
+mod kconfig;
+mod maintainers;
 mod makefile;
 mod metadata;
+mod modalias;
+mod rust;
 mod source;
+mod symbols;
+mod tree;
 
 #[cfg(any(feature = "json", feature = "cbor"))]
 mod io;
@@ -14,8 +31,23 @@ pub(crate) use makefile::{MakeFile, MakeStmt};
 pub(crate) use std::path::{Path, PathBuf};
 
 pub use anyhow::{Error, Result};
+pub use condition::Condition;
+pub use diff::{ConfigOptDiff, MetaDataDiff};
+pub use dotconfig::{DotConfig, Tristate};
 pub use filemgr::{File, FileMgr};
-pub use metadata::{CompatStrData, ConfigOptData, MetaData, ModuleData, ParamData, SourceData};
+pub use metadata::{
+    BindingData, CompatStrData, ConfigOptData, ConfigOptType, DeviceBus, DeviceData, DeviceIdData,
+    DeviceTableData, DtNodeData, DtSourceData, ExportData, FileStamp, HeaderData, KmodData,
+    MaintainersData, MetaData, ModuleData, ParamData, SourceData, SourceLang, SourceLoc,
+    SymbolData,
+};
+pub use tree::{DirTree, MemTree, SourceTree};
+
+#[cfg(feature = "archive")]
+pub use archive::is_archive;
+
+#[cfg(feature = "git")]
+pub use git::GitTree;
 
 #[cfg(any(feature = "json", feature = "cbor"))]
 pub use io::{DataCoding, DataCompress, DataOptions};
@@ -0,0 +1,315 @@
+use crate::{
+    modalias::wildcard_match, DeviceBus, FileMgr, MaintainersData, MetaData, Path, Result,
+};
+use std::collections::BTreeMap as Map;
+use tokio::io::AsyncReadExt;
+
+impl MetaData {
+    /// Index sections of MAINTAINERS file
+    pub(crate) async fn index_maintainers(&mut self, filemgr: &FileMgr) -> Result<()> {
+        let path = Path::new("MAINTAINERS");
+
+        if !filemgr.file_exists(path).await? {
+            log::warn!("Missing MAINTAINERS file");
+            return Ok(());
+        }
+
+        let mut file = filemgr.open(path).await?;
+        let mut src = String::new();
+
+        file.read_to_string(&mut src).await?;
+
+        let mut section: Option<MaintainersData> = None;
+
+        for line in src.lines() {
+            let line = line.trim_end();
+
+            if line.is_empty() {
+                if let Some(section) = section.take() {
+                    self.add_maintainers(section);
+                }
+                continue;
+            }
+
+            let field = line
+                .split_once(':')
+                .filter(|(tag, _)| {
+                    tag.len() == 1 && tag.starts_with(|c: char| c.is_ascii_uppercase())
+                })
+                .map(|(tag, value)| (tag, value.trim()));
+
+            match field {
+                None if !line.starts_with(char::is_whitespace) => {
+                    if let Some(section) = section.take() {
+                        self.add_maintainers(section);
+                    }
+                    section = Some(MaintainersData {
+                        name: line.into(),
+                        ..Default::default()
+                    });
+                }
+                Some((tag, value)) => {
+                    let section = if let Some(section) = &mut section {
+                        section
+                    } else {
+                        continue;
+                    };
+                    let value = value.to_string();
+                    match tag {
+                        "M" => section.maintainers.push(value),
+                        "R" => section.reviewers.push(value),
+                        "L" => section.lists.push(value),
+                        "S" => section.status = value,
+                        "F" | "X" => {
+                            // directories should match all files inside
+                            let value = if !value.ends_with('/')
+                                && !value.contains(['*', '?'])
+                                && filemgr.dir_exists(&value).await?
+                            {
+                                format!("{}/", value)
+                            } else {
+                                value
+                            };
+                            if tag == "F" {
+                                section.files.push(value);
+                            } else {
+                                section.excludes.push(value);
+                            }
+                        }
+                        "N" => section.regexes.push(value),
+                        "K" => section.keywords.push(value),
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(section) = section {
+            self.add_maintainers(section);
+        }
+
+        Ok(())
+    }
+
+    fn add_maintainers(&mut self, section: MaintainersData) {
+        // skip preamble and other sections without fields
+        if !section.is_empty() {
+            self.maintainers.push(section);
+        }
+    }
+
+    /// Find MAINTAINERS sections which covers file
+    ///
+    /// Sections ordered by specificity (depth of matching pattern) like in `get_maintainer.pl`.
+    pub fn maintainers_of_path(&self, path: impl AsRef<Path>) -> Vec<&MaintainersData> {
+        let path = path.as_ref().to_string_lossy();
+
+        self.sort_maintainers(
+            self.maintainers
+                .iter()
+                .enumerate()
+                .filter_map(|(index, section)| {
+                    section.path_depth(&path).map(|depth| (index, depth))
+                }),
+        )
+    }
+
+    /// Find MAINTAINERS sections by source path, compatible string or configuration option
+    ///
+    /// Sections of related sources, binding schemas and Kconfig files are taken into account.
+    ///
+    /// Unlike `get_maintainer.pl` keywords (`K:`) is matched against query itself
+    /// but not against contents of related sources which is not stored in index.
+    pub fn maintainers_of(&self, query: impl AsRef<str>) -> Vec<&MaintainersData> {
+        let query = query.as_ref();
+        let config_opt = query.strip_prefix("CONFIG_").unwrap_or(query);
+
+        let paths = if let Some(config_data) = self.config_opt(config_opt) {
+            config_data
+                .sources
+                .iter()
+                .cloned()
+                .chain(config_data.locations.iter().map(|loc| loc.file.clone()))
+                .collect::<Vec<_>>()
+        } else if self.compat_str(query).is_some() || self.device(DeviceBus::Of, query).is_some() {
+            self.compat_str_sources(query)
+                .into_iter()
+                .cloned()
+                .chain(
+                    self.compat_str(query)
+                        .into_iter()
+                        .flat_map(|compat_data| compat_data.bindings.iter().cloned()),
+                )
+                .collect()
+        } else {
+            return self.maintainers_of_path(query);
+        };
+
+        let mut depths = Map::<usize, usize>::default();
+
+        for path in &paths {
+            let path = path.to_string_lossy();
+            for (index, section) in self.maintainers.iter().enumerate() {
+                if let Some(depth) = section.path_depth(&path) {
+                    let entry = depths.entry(index).or_default();
+                    *entry = (*entry).max(depth);
+                }
+            }
+        }
+
+        // keywords usually matches compatible strings and symbols
+        for (index, section) in self.maintainers.iter().enumerate() {
+            if section.keywords().iter().any(|regex| regex.is_match(query)) {
+                depths.entry(index).or_default();
+            }
+        }
+
+        self.sort_maintainers(depths.into_iter())
+    }
+
+    fn sort_maintainers(
+        &self,
+        matches: impl Iterator<Item = (usize, usize)>,
+    ) -> Vec<&MaintainersData> {
+        let mut matches = matches.collect::<Vec<_>>();
+
+        matches.sort_by(|(a_index, a_depth), (b_index, b_depth)| {
+            b_depth.cmp(a_depth).then(a_index.cmp(b_index))
+        });
+
+        matches
+            .into_iter()
+            .map(|(index, _)| &self.maintainers[index])
+            .collect()
+    }
+}
+
+impl MaintainersData {
+    /// Check that section has no fields
+    pub fn is_empty(&self) -> bool {
+        self.maintainers.is_empty()
+            && self.reviewers.is_empty()
+            && self.lists.is_empty()
+            && self.status.is_empty()
+            && self.files.is_empty()
+            && self.regexes.is_empty()
+            && self.keywords.is_empty()
+    }
+
+    /// Get compiled file name regexes (`N:`)
+    fn regexes(&self) -> &[regex::Regex] {
+        self.compiled_regexes
+            .get_or_init(|| compile_regexes(&self.regexes))
+    }
+
+    /// Get compiled keyword regexes (`K:`)
+    fn keywords(&self) -> &[regex::Regex] {
+        self.compiled_keywords
+            .get_or_init(|| compile_regexes(&self.keywords))
+    }
+
+    /// Get depth of pattern which matches file path
+    ///
+    /// Returns `None` when section does not cover file.
+    fn path_depth(&self, path: &str) -> Option<usize> {
+        if self
+            .excludes
+            .iter()
+            .any(|pattern| file_pattern_match(pattern, path))
+        {
+            return None;
+        }
+
+        self.files
+            .iter()
+            .filter(|pattern| file_pattern_match(pattern, path))
+            .map(|pattern| pattern.trim_end_matches('/').matches('/').count() + 1)
+            .max()
+            .or_else(|| {
+                // regex matches considered as least specific
+                self.regexes()
+                    .iter()
+                    .any(|regex| regex.is_match(path))
+                    .then_some(0)
+            })
+    }
+}
+
+/// Match file path using `F:` or `X:` pattern
+///
+/// Patterns which ends with `/` matches all files in directory recursively,
+/// other patterns matches files in same directory only.
+fn file_pattern_match(pattern: &str, path: &str) -> bool {
+    if pattern.ends_with('/') {
+        wildcard_match(&format!("{}*", pattern), path)
+    } else {
+        pattern.matches('/').count() == path.matches('/').count() && wildcard_match(pattern, path)
+    }
+}
+
+/// Compile regexes skipping invalid ones
+fn compile_regexes(regexes: &[String]) -> Vec<regex::Regex> {
+    regexes
+        .iter()
+        .filter_map(|regex| {
+            regex::Regex::new(regex)
+                .map_err(|error| log::warn!("Invalid regex \"{}\": {}", regex, error))
+                .ok()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::MemTree;
+
+    #[test]
+    fn regexes_and_keywords() {
+        let section = MaintainersData {
+            name: "ARM SMMU DRIVERS".into(),
+            regexes: vec!["smmu".into(), "(".into()],
+            keywords: vec![r"\barm,smmu".into()],
+            ..Default::default()
+        };
+
+        assert_eq!(section.regexes().len(), 1);
+        assert_eq!(section.path_depth("drivers/iommu/arm-smmu.c"), Some(0));
+        assert_eq!(section.path_depth("drivers/iommu/iommu.c"), None);
+        assert!(section.keywords()[0].is_match("arm,smmu-v2"));
+    }
+
+    #[tokio::test]
+    async fn maintainers_of() {
+        let tree = MemTree::new("linux");
+
+        tree.add_file(
+            "MAINTAINERS",
+            "ACME FOO DRIVER\nM:\tJane Doe <jane@example.com>\nS:\tMaintained\nF:\tdrivers/foo\nK:\tacme,foo\n\nDRIVER CORE\nM:\tGreg <greg@example.com>\nS:\tSupported\nF:\tdrivers/base/\n",
+        );
+        tree.add_file("drivers/foo/foo.c", "");
+
+        let mut metadata = MetaData::default();
+        metadata
+            .index_maintainers(&FileMgr::from_tree(tree))
+            .await
+            .unwrap();
+
+        let source_data = metadata.source_mut("drivers/foo/foo.c");
+        source_data.compat_strs.insert("acme,foo".into());
+        source_data.config_opts.insert("FOO".into());
+        metadata.sync_with_sources();
+
+        // directory without trailing slash matches files inside
+        assert_eq!(metadata.maintainers[0].files, ["drivers/foo/"]);
+
+        for query in ["drivers/foo/foo.c", "acme,foo", "CONFIG_FOO"] {
+            assert_eq!(metadata.maintainers_of(query)[0].name, "ACME FOO DRIVER");
+        }
+        assert_eq!(
+            metadata.maintainers_of("drivers/base/core.c")[0].name,
+            "DRIVER CORE"
+        );
+    }
+}
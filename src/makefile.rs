@@ -1,54 +1,62 @@
-use crate::{File, FileMgr, Path, Result};
+use crate::{Condition, File, Result};
 use std::collections::{HashMap as Map, VecDeque as Deque};
 
 struct Orphan {
     elements: Vec<String>,
-    conditions: Vec<String>,
+    condition: Condition,
 }
 
 pub struct MakeFile {
     lines: tokio::io::Lines<tokio::io::BufReader<File>>,
-    // module_name => condition[]
-    modules: Map<String, Vec<String>>,
-    // module_name => (elements, conditions)[]
+    // module_name => condition
+    modules: Map<String, Condition>,
+    // module_name => (elements, condition)[]
     orphans: Map<String, Deque<Orphan>>,
     // unprocessed module
     module: Option<String>,
 }
 
 impl MakeFile {
-    pub async fn parse(filemgr: &FileMgr, path: impl AsRef<Path>) -> Result<Self> {
+    /// Parse already opened kbuild file
+    pub fn from_file(file: File) -> Self {
         use tokio::io::{AsyncBufReadExt, BufReader};
 
-        log::debug!("parse kbuild file: {:?}", path.as_ref());
-
-        let file = filemgr.open(&path).await?;
-        let lines = BufReader::new(file).lines();
-
-        Ok(Self {
-            lines,
+        Self {
+            lines: BufReader::new(file).lines(),
             modules: Map::default(),
             orphans: Map::default(),
             module: None,
-        })
+        }
+    }
+
+    /// Get all remaining statements
+    pub async fn stmts(mut self) -> Result<Vec<MakeStmt>> {
+        let mut stmts = Vec::default();
+
+        while let Some(stmt) = self.next_stmt().await? {
+            stmts.push(stmt);
+        }
+
+        Ok(stmts)
     }
 
     pub async fn next_stmt(&mut self) -> Result<Option<MakeStmt>> {
         if let Some(prefix) = &self.module {
-            if let (Some(module_conditions), Some(queue)) =
+            if let (Some(module_condition), Some(queue)) =
                 (self.modules.get(prefix), self.orphans.get_mut(prefix))
             {
                 if let Some(Orphan {
                     elements,
-                    mut conditions,
+                    condition,
                 }) = queue.pop_front()
                 {
-                    conditions.extend(module_conditions.clone());
+                    let condition = module_condition.clone() & condition;
                     let prefix = prefix.clone();
                     return Ok(Some(MakeStmt::Var {
                         prefix,
                         elements,
-                        conditions,
+                        condition,
+                        builtin: None,
                     }));
                 }
                 self.orphans.remove(prefix);
@@ -80,7 +88,8 @@ impl MakeFile {
                     if let MakeStmt::Var {
                         prefix,
                         elements,
-                        conditions,
+                        condition,
+                        ..
                     } = &mut stmt
                     {
                         if ["obj", "lib", "subdir", "core", "drivers"]
@@ -92,22 +101,22 @@ impl MakeFile {
                                     if self.orphans.contains_key(module) {
                                         self.module = Some(module.into());
                                     }
-                                    self.modules.insert(module.into(), conditions.clone());
+                                    self.modules.insert(module.into(), condition.clone());
                                 }
                             }
-                        } else if let Some(module_conditions) = self.modules.get(prefix) {
-                            conditions.extend(module_conditions.clone());
+                        } else if let Some(module_condition) = self.modules.get(prefix) {
+                            *condition = module_condition.clone() & core::mem::take(condition);
                         } else {
                             if let Some(queue) = self.orphans.get_mut(prefix) {
                                 queue.push_back(Orphan {
                                     elements: elements.clone(),
-                                    conditions: conditions.clone(),
+                                    condition: condition.clone(),
                                 });
                             } else {
                                 let mut queue = Deque::default();
                                 queue.push_back(Orphan {
                                     elements: elements.clone(),
-                                    conditions: conditions.clone(),
+                                    condition: condition.clone(),
                                 });
                                 self.orphans.insert(prefix.clone(), queue);
                             }
@@ -131,24 +140,39 @@ impl MakeFile {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MakeStmt {
+    #[cfg_attr(feature = "serde", serde(rename = "v"))]
     Var {
+        #[cfg_attr(feature = "serde", serde(rename = "p"))]
         prefix: String,
-        conditions: Vec<String>,
+        #[cfg_attr(feature = "serde", serde(rename = "c"))]
+        condition: Condition,
+        #[cfg_attr(feature = "serde", serde(rename = "e"))]
         elements: Vec<String>,
+        /// `Some(true)` for `<prefix>-y`, `Some(false)` for `<prefix>-m`
+        #[cfg_attr(feature = "serde", serde(rename = "b"))]
+        builtin: Option<bool>,
     },
+    #[cfg_attr(feature = "serde", serde(rename = "i"))]
     If {
-        conditions: Vec<String>,
+        #[cfg_attr(feature = "serde", serde(rename = "c"))]
+        condition: Condition,
     },
+    #[cfg_attr(feature = "serde", serde(rename = "ei"))]
     ElseIf {
-        conditions: Vec<String>,
+        #[cfg_attr(feature = "serde", serde(rename = "c"))]
+        condition: Condition,
     },
+    #[cfg_attr(feature = "serde", serde(rename = "el"))]
+    Else,
+    #[cfg_attr(feature = "serde", serde(rename = "en"))]
     EndIf,
 }
 
 impl MakeStmt {
-    fn parse_conditions(st: &str) -> Vec<String> {
+    fn parse_symbols(st: &str) -> Vec<String> {
         st.split("$(CONFIG_")
             .skip(1)
             .filter_map(|cond| cond.split_once(')').map(|(var, _)| var.trim().to_string()))
@@ -156,6 +180,85 @@ impl MakeStmt {
             .collect()
     }
 
+    fn parse_conditions(st: &str) -> Condition {
+        Condition::all(Self::parse_symbols(st).into_iter().map(Condition::Symbol))
+    }
+
+    /// Parse condition of `ifdef` / `ifndef` directive
+    fn parse_ifdef(st: &str, negated: bool) -> Condition {
+        let condition = if let Some((_, symbol)) = st.trim().split_once("CONFIG_") {
+            Condition::Symbol(symbol.trim().into())
+        } else {
+            // not related to configuration options
+            return Condition::default();
+        };
+
+        if negated {
+            !condition
+        } else {
+            condition
+        }
+    }
+
+    /// Parse condition of `ifeq` / `ifneq` directive
+    fn parse_ifeq(st: &str, negated: bool) -> Condition {
+        let st = st.trim();
+
+        let args = if let Some(st) = st.strip_prefix('(').and_then(|st| st.strip_suffix(')')) {
+            // ifeq (arg1,arg2)
+            let mut depth = 0usize;
+            st.char_indices()
+                .find(|(_, c)| {
+                    match c {
+                        '(' => depth += 1,
+                        ')' => depth = depth.saturating_sub(1),
+                        ',' if depth == 0 => return true,
+                        _ => {}
+                    }
+                    false
+                })
+                .map(|(idx, _)| (&st[..idx], &st[idx + 1..]))
+        } else {
+            // ifeq "arg1" "arg2"
+            let mut args = st.split(['"', '\'']).skip(1).step_by(2);
+            args.next().zip(args.next())
+        };
+
+        let condition = if let Some((lhs, rhs)) = args {
+            let (expr, value) = if !rhs.contains("$(") {
+                (lhs.trim(), rhs.trim())
+            } else {
+                (rhs.trim(), lhs.trim())
+            };
+
+            let symbols = Self::parse_symbols(expr);
+
+            if symbols.is_empty() {
+                // not related to configuration options
+                return Condition::default();
+            }
+
+            if value.contains("$(") {
+                Condition::all(symbols.into_iter().map(Condition::Symbol))
+            } else if value.is_empty() || value == "n" {
+                // all options are disabled
+                !Condition::any(symbols.into_iter().map(Condition::Symbol))
+            } else if symbols.len() == 1 && expr == format!("$(CONFIG_{})", symbols[0]) {
+                Condition::Equal(symbols.into_iter().next().unwrap(), value.into())
+            } else {
+                Condition::all(symbols.into_iter().map(Condition::Symbol))
+            }
+        } else {
+            Self::parse_conditions(st)
+        };
+
+        if negated && !condition.is_true() {
+            !condition
+        } else {
+            condition
+        }
+    }
+
     fn parse_elements<'a>(pfx: &str, st: &'a str) -> Vec<String> {
         st.split(char::is_whitespace)
             .filter_map(if pfx != "subdir" {
@@ -192,42 +295,51 @@ impl MakeStmt {
             return Ok(Some(Self::EndIf));
         }
 
-        if line.starts_with("else ") {
-            let line = line.split_once(char::is_whitespace).unwrap().1;
+        if let Some(line) = line
+            .strip_prefix("else")
+            .filter(|line| line.is_empty() || line.starts_with(char::is_whitespace))
+        {
+            let line = line.trim_start();
+
+            if line.is_empty() || line.starts_with('#') {
+                return Ok(Some(Self::Else));
+            }
 
             if line.starts_with("ifdef ") || line.starts_with("ifndef ") {
                 let cond = line.split_once(char::is_whitespace).unwrap().1;
-                if let Some((_, cond)) = cond.split_once("CONFIG_") {
-                    let conditions = vec![cond.into()];
-                    return Ok(Some(Self::ElseIf { conditions }));
-                }
+                let condition = Self::parse_ifdef(cond, line.starts_with("ifndef "));
+
+                return Ok(Some(Self::ElseIf { condition }));
             } else if line.starts_with("ifeq ") || line.starts_with("ifneq ") {
-                let conditions =
-                    Self::parse_conditions(line.split_once(char::is_whitespace).unwrap().1);
+                let cond = line.split_once(char::is_whitespace).unwrap().1;
+                let condition = Self::parse_ifeq(cond, line.starts_with("ifneq "));
 
-                return Ok(Some(Self::ElseIf { conditions }));
+                return Ok(Some(Self::ElseIf { condition }));
             }
         } else if line.starts_with("ifdef ") || line.starts_with("ifndef ") {
             let cond = line.split_once(char::is_whitespace).unwrap().1;
-            if let Some((_, cond)) = cond.split_once("CONFIG_") {
-                let conditions = vec![cond.into()];
-                return Ok(Some(Self::If { conditions }));
-            }
+            let condition = Self::parse_ifdef(cond, line.starts_with("ifndef "));
+
+            return Ok(Some(Self::If { condition }));
         } else if line.starts_with("ifeq ") || line.starts_with("ifneq ") {
-            let conditions =
-                Self::parse_conditions(line.split_once(char::is_whitespace).unwrap().1);
+            let cond = line.split_once(char::is_whitespace).unwrap().1;
+            let condition = Self::parse_ifeq(cond, line.starts_with("ifneq "));
 
-            return Ok(Some(Self::If { conditions }));
+            return Ok(Some(Self::If { condition }));
         } else if let Some((pfx, key, val)) = line.split_once('=').and_then(|(var, val)| {
             split_once_if(var, "-", |_, sfx| {
                 sfx.starts_with("$(") || !sfx.contains('-')
             })
             .map(|(pfx, key)| (pfx, key, val))
         }) {
-            let conditions =
-                Self::parse_conditions(key.trim_end_matches(|c: char| {
-                    c == '+' || c == ':' || c == '?' || c.is_whitespace()
-                }));
+            let key = key
+                .trim_end_matches(|c: char| c == '+' || c == ':' || c == '?' || c.is_whitespace());
+            let condition = Self::parse_conditions(key);
+            let builtin = match key {
+                "y" => Some(true),
+                "m" => Some(false),
+                _ => None,
+            };
 
             let elements = Self::parse_elements(pfx, val.trim_start());
 
@@ -237,8 +349,9 @@ impl MakeStmt {
 
             return Ok(Some(Self::Var {
                 prefix: pfx.into(),
-                conditions,
+                condition,
                 elements,
+                builtin,
             }));
         }
 
@@ -260,3 +373,83 @@ fn split_once_if<'a>(
     }
     None
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn if_condition(line: &str) -> String {
+        match MakeStmt::parse(line).unwrap() {
+            Some(MakeStmt::If { condition } | MakeStmt::ElseIf { condition }) => {
+                condition.to_string()
+            }
+            stmt => panic!("Unexpected statement: {:?}", stmt),
+        }
+    }
+
+    #[test]
+    fn ifdef() {
+        assert_eq!(if_condition("ifdef CONFIG_PCI"), "PCI");
+        assert_eq!(if_condition("ifndef CONFIG_MMU"), "!MMU");
+        // not related to configuration options
+        assert_eq!(if_condition("ifdef KBUILD_EXTMOD"), "y");
+    }
+
+    #[test]
+    fn ifeq() {
+        assert_eq!(if_condition("ifeq ($(CONFIG_ARM64),y)"), "ARM64=y");
+        assert_eq!(if_condition("ifeq ($(CONFIG_NET), m)"), "NET=m");
+        assert_eq!(if_condition("ifeq \"$(CONFIG_X86)\" \"y\""), "X86=y");
+        assert_eq!(if_condition("ifneq ($(CONFIG_OF),)"), "OF");
+        assert_eq!(if_condition("ifeq ($(CONFIG_OF),)"), "!OF");
+        assert_eq!(if_condition("ifneq ($(CONFIG_ACPI),y)"), "!ACPI=y");
+        assert_eq!(if_condition("ifneq ($(CONFIG_A)$(CONFIG_B),)"), "A || B");
+        // not related to configuration options
+        assert_eq!(if_condition("ifeq ($(ARCH),arm)"), "y");
+    }
+
+    #[test]
+    fn else_branches() {
+        assert!(matches!(
+            MakeStmt::parse("else").unwrap(),
+            Some(MakeStmt::Else)
+        ));
+        assert!(matches!(
+            MakeStmt::parse("else # comment").unwrap(),
+            Some(MakeStmt::Else)
+        ));
+        assert_eq!(if_condition("else ifeq ($(CONFIG_SMP),y)"), "SMP=y");
+        assert_eq!(if_condition("else ifdef CONFIG_NUMA"), "NUMA");
+        assert!(matches!(
+            MakeStmt::parse("endif").unwrap(),
+            Some(MakeStmt::EndIf)
+        ));
+    }
+
+    #[test]
+    fn var() {
+        match MakeStmt::parse("obj-$(CONFIG_ARM_SMMU) += arm_smmu.o").unwrap() {
+            Some(MakeStmt::Var {
+                prefix,
+                condition,
+                elements,
+                builtin,
+            }) => {
+                assert_eq!(prefix, "obj");
+                assert_eq!(condition.to_string(), "ARM_SMMU");
+                assert_eq!(elements, ["arm_smmu.o"]);
+                assert_eq!(builtin, None);
+            }
+            stmt => panic!("Unexpected statement: {:?}", stmt),
+        }
+        match MakeStmt::parse("arm_smmu-objs += arm-smmu.o arm-smmu-impl.o").unwrap() {
+            Some(MakeStmt::Var {
+                prefix, elements, ..
+            }) => {
+                assert_eq!(prefix, "arm_smmu");
+                assert_eq!(elements, ["arm-smmu.o", "arm-smmu-impl.o"]);
+            }
+            stmt => panic!("Unexpected statement: {:?}", stmt),
+        }
+    }
+}
@@ -1,17 +1,31 @@
-use crate::{Path, PathBuf};
+use crate::{Condition, Error, MakeStmt, Path, PathBuf, Result};
 use std::collections::{BTreeMap as Map, BTreeSet as Set};
 
 /// Data associated with source file
 #[derive(Debug, Default, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SourceData {
-    /// Configuration options associated with source file
+    /// Source language
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "l", default, skip_serializing_if = "SourceLang::is_c")
+    )]
+    pub lang: SourceLang,
+
+    /// Configuration options which should be enabled to build source file
     #[cfg_attr(
         feature = "serde",
         serde(rename = "o", default, skip_serializing_if = "Set::is_empty")
     )]
     pub config_opts: Set<String>,
 
+    /// Condition to build source file
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "c", default, skip_serializing_if = "Condition::is_true")
+    )]
+    pub condition: Condition,
+
     /// Compatible strings of source file
     #[cfg_attr(
         feature = "serde",
@@ -19,6 +33,34 @@ pub struct SourceData {
     )]
     pub compat_strs: Set<String>,
 
+    /// Symbols exported by source file
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "e", default, skip_serializing_if = "Map::is_empty")
+    )]
+    pub exports: Map<String, ExportData>,
+
+    /// Exported symbols used by source file
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "r", default, skip_serializing_if = "Set::is_empty")
+    )]
+    pub symbol_refs: Set<String>,
+
+    /// Device match tables of source file
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "t", default, skip_serializing_if = "Map::is_empty")
+    )]
+    pub device_tables: Map<String, DeviceTableData>,
+
+    /// Files included by source file
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "i", default, skip_serializing_if = "Set::is_empty")
+    )]
+    pub includes: Set<PathBuf>,
+
     /// Module data
     #[cfg_attr(
         feature = "serde",
@@ -27,10 +69,199 @@ pub struct SourceData {
     pub module: Option<ModuleData>,
 }
 
+/// Data associated with exported symbol
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExportData {
+    /// Symbol exported for GPL-compatible modules only
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "g", default, skip_serializing_if = "core::ops::Not::not")
+    )]
+    pub gpl: bool,
+
+    /// Symbol namespace
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "n", default, skip_serializing_if = "String::is_empty")
+    )]
+    pub namespace: String,
+}
+
+/// Device bus of match table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, educe::Educe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+#[educe(Default)]
+pub enum DeviceBus {
+    /// Devicetree (`of_device_id`)
+    #[educe(Default)]
+    Of,
+
+    /// ACPI (`acpi_device_id`)
+    Acpi,
+
+    /// PCI (`pci_device_id`)
+    Pci,
+
+    /// USB (`usb_device_id`)
+    Usb,
+
+    /// I2C (`i2c_device_id`)
+    I2c,
+
+    /// SPI (`spi_device_id`)
+    Spi,
+
+    /// Platform (`platform_device_id`)
+    Platform,
+}
+
+/// Data associated with device match table
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeviceTableData {
+    /// Device bus
+    #[cfg_attr(feature = "serde", serde(rename = "b"))]
+    pub bus: DeviceBus,
+
+    /// Table is exported using `MODULE_DEVICE_TABLE`
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "x", default, skip_serializing_if = "core::ops::Not::not")
+    )]
+    pub exported: bool,
+
+    /// Table entries
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "e", default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub entries: Vec<DeviceIdData>,
+}
+
+/// Data associated with device match table entry
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeviceIdData {
+    /// Device identifier (like compatible string or `vendor:device` for PCI and USB)
+    #[cfg_attr(feature = "serde", serde(rename = "i"))]
+    pub id: String,
+
+    /// Identifier used to initialize driver data
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "d", default, skip_serializing_if = "String::is_empty")
+    )]
+    pub data: String,
+}
+
+/// Data associated with header file
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HeaderData {
+    /// Files included by header file
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "i", default, skip_serializing_if = "Set::is_empty")
+    )]
+    pub includes: Set<PathBuf>,
+
+    /// Sources and headers which includes header file
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub included_by: Set<PathBuf>,
+}
+
+/// Modification stamp of indexed file
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FileStamp {
+    /// Modification time (nanoseconds since UNIX epoch)
+    #[cfg_attr(feature = "serde", serde(rename = "m"))]
+    pub mtime: u64,
+
+    /// File size in bytes
+    #[cfg_attr(feature = "serde", serde(rename = "s"))]
+    pub size: u64,
+
+    /// Hash of file contents (FNV-1a)
+    #[cfg_attr(feature = "serde", serde(rename = "h"))]
+    pub hash: u64,
+}
+
+/// Data associated with devicetree source file
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DtSourceData {
+    /// Files included by devicetree source file in include order
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "i", default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub includes: Vec<PathBuf>,
+
+    /// Nodes by node path or reference
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "n", default, skip_serializing_if = "Map::is_empty")
+    )]
+    pub nodes: Map<String, DtNodeData>,
+
+    /// Node paths by label
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "l", default, skip_serializing_if = "Map::is_empty")
+    )]
+    pub labels: Map<String, String>,
+}
+
+/// Data associated with devicetree node
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DtNodeData {
+    /// Compatible strings
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "c", default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub compat_strs: Vec<String>,
+
+    /// Node status (like `okay` or `disabled`)
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "s", default, skip_serializing_if = "Option::is_none")
+    )]
+    pub status: Option<String>,
+}
+
+/// Source language
+#[derive(Debug, Clone, Copy, PartialEq, Eq, educe::Educe)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+#[educe(Default)]
+pub enum SourceLang {
+    /// C source (.c)
+    #[educe(Default)]
+    C,
+
+    /// Assembler source (.S)
+    Asm,
+
+    /// Rust source (.rs)
+    Rust,
+}
+
 /// Data associated with module
 #[derive(Debug, Default, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ModuleData {
+    /// Module name (Rust modules only)
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "n", default, skip_serializing_if = "String::is_empty")
+    )]
+    pub name: String,
+
     /// Module authors
     #[cfg_attr(
         feature = "serde",
@@ -65,6 +296,13 @@ pub struct ModuleData {
         serde(rename = "p", default, skip_serializing_if = "Map::is_empty")
     )]
     pub params: Map<String, ParamData>,
+
+    /// Imported symbol namespaces
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "i", default, skip_serializing_if = "Set::is_empty")
+    )]
+    pub imports: Set<String>,
 }
 
 /// Data associated with parameter
@@ -85,40 +323,369 @@ pub struct ParamData {
         serde(rename = "d", default, skip_serializing_if = "String::is_empty")
     )]
     pub description: String,
+
+    /// Source file which defines parameter (merged module data only)
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "f", default, skip_serializing_if = "Option::is_none")
+    )]
+    pub source: Option<PathBuf>,
+}
+
+/// Location in source file
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SourceLoc {
+    /// Source file path
+    #[cfg_attr(feature = "serde", serde(rename = "f"))]
+    pub file: PathBuf,
+
+    /// Line number (starting from 1)
+    #[cfg_attr(feature = "serde", serde(rename = "l"))]
+    pub line: u32,
+}
+
+/// Configuration option type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum ConfigOptType {
+    /// Boolean option (y/n)
+    Bool,
+
+    /// Tristate option (y/m/n)
+    Tristate,
+
+    /// Integer option
+    Int,
+
+    /// Hexadecimal option
+    Hex,
+
+    /// String option
+    String,
 }
 
 /// Data related to configuration option
 #[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ConfigOptData {
     /// Source files related to configuration option
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub sources: Set<PathBuf>,
+
+    /// Configuration option type
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "t", default, skip_serializing_if = "Option::is_none")
+    )]
+    pub type_: Option<ConfigOptType>,
+
+    /// Configuration option prompt
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "p", default, skip_serializing_if = "String::is_empty")
+    )]
+    pub prompt: String,
+
+    /// Configuration option help text
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "h", default, skip_serializing_if = "String::is_empty")
+    )]
+    pub help: String,
+
+    /// Locations of configuration option definitions in Kconfig files
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "l", default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub locations: Vec<SourceLoc>,
+
+    /// Dependencies of option (including dependencies of enclosing menus and if-blocks)
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "d", default, skip_serializing_if = "Condition::is_true")
+    )]
+    pub depends_on: Condition,
+
+    /// Configuration options which this option selects with conditions of selection
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "s", default, skip_serializing_if = "Map::is_empty")
+    )]
+    pub selects: Map<String, Condition>,
+
+    /// Configuration options which this option implies with conditions of implication
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "i", default, skip_serializing_if = "Map::is_empty")
+    )]
+    pub implies: Map<String, Condition>,
+
+    /// Configuration options which selects this option
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub selected_by: Set<String>,
 }
 
 /// Data associated with compatible string
 #[derive(Debug, Default, Clone)]
 pub struct CompatStrData {
-    /// Source file associated with compatible string
-    pub source: PathBuf,
+    /// Source files associated with compatible string
+    pub sources: Set<PathBuf>,
+
+    /// Binding schemas which describes compatible string
+    pub bindings: Set<PathBuf>,
 }
 
-/// Source-code metadata
+/// Data associated with devicetree binding schema
 #[derive(Debug, Default, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BindingData {
+    /// Schema identifier (`$id`)
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "i", default, skip_serializing_if = "String::is_empty")
+    )]
+    pub id: String,
+
+    /// Schema title
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "t", default, skip_serializing_if = "String::is_empty")
+    )]
+    pub title: String,
+
+    /// Schema maintainers
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "m", default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub maintainers: Vec<String>,
+
+    /// Compatible strings described by schema
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "c", default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub compat_strs: Vec<String>,
+}
+
+/// Data associated with symbol
+#[derive(Debug, Default, Clone)]
+pub struct SymbolData {
+    /// Source files which exports symbol
+    pub exported_by: Set<PathBuf>,
+
+    /// Source files which uses symbol
+    pub used_by: Set<PathBuf>,
+}
+
+/// Data associated with section of MAINTAINERS file
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MaintainersData {
+    /// Section name (subsystem or driver)
+    #[cfg_attr(feature = "serde", serde(rename = "n"))]
+    pub name: String,
+
+    /// Maintainers (`M:`)
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "m", default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub maintainers: Vec<String>,
+
+    /// Reviewers (`R:`)
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "r", default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub reviewers: Vec<String>,
+
+    /// Mailing lists (`L:`)
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "l", default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub lists: Vec<String>,
+
+    /// Status (`S:`)
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "s", default, skip_serializing_if = "String::is_empty")
+    )]
+    pub status: String,
+
+    /// File patterns (`F:`)
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "f", default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub files: Vec<String>,
+
+    /// Excluded file patterns (`X:`)
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "x", default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub excludes: Vec<String>,
+
+    /// File name regexes (`N:`)
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "e", default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub regexes: Vec<String>,
+
+    /// Content keyword regexes (`K:`)
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "k", default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub keywords: Vec<String>,
+
+    /// Compiled file name regexes
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) compiled_regexes: std::sync::OnceLock<Vec<regex::Regex>>,
+
+    /// Compiled keyword regexes
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) compiled_keywords: std::sync::OnceLock<Vec<regex::Regex>>,
+}
+
+/// Data associated with device identifier
+#[derive(Debug, Default, Clone)]
+pub struct DeviceData {
+    /// Source files associated with device identifier
+    pub sources: Set<PathBuf>,
+}
+
+/// Data associated with kernel module (`.ko` object)
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KmodData {
+    /// Source files linked into module
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "s", default, skip_serializing_if = "Set::is_empty")
+    )]
+    pub sources: Set<PathBuf>,
+
+    /// Condition to build module
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "c", default, skip_serializing_if = "Condition::is_true")
+    )]
+    pub condition: Condition,
+
+    /// Built-in (`obj-y`) or loadable (`obj-m`) module, `None` when depends on configuration
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "b", default, skip_serializing_if = "Option::is_none")
+    )]
+    pub builtin: Option<bool>,
+}
+
+/// Source-code metadata
+#[derive(Debug, Clone, educe::Educe)]
+#[educe(Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MetaData {
+    /// Format version of index (missing in indexes created before versioning)
+    #[educe(Default(expression = "MetaData::FORMAT_VERSION"))]
+    #[cfg_attr(feature = "serde", serde(rename = "v", default))]
+    pub version: u32,
+
     /// Data associated with source files
-    #[cfg_attr(feature = "serde", serde(flatten))]
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "s", default, skip_serializing_if = "Map::is_empty")
+    )]
     pub sources: Map<PathBuf, SourceData>,
 
     /// Data associated with configuration options
-    #[cfg_attr(feature = "serde", serde(skip))]
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "o", default, skip_serializing_if = "Map::is_empty")
+    )]
     pub config_opts: Map<String, ConfigOptData>,
 
     /// Data associated with compatible strings
     #[cfg_attr(feature = "serde", serde(skip))]
     pub compat_strs: Map<String, CompatStrData>,
+
+    /// Data associated with device identifiers
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub devices: Map<DeviceBus, Map<String, DeviceData>>,
+
+    /// Data associated with exported symbols
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub symbols: Map<String, SymbolData>,
+
+    /// Data associated with header files
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "h", default, skip_serializing_if = "Map::is_empty")
+    )]
+    pub headers: Map<PathBuf, HeaderData>,
+
+    /// Data associated with devicetree source files
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "d", default, skip_serializing_if = "Map::is_empty")
+    )]
+    pub dt_sources: Map<PathBuf, DtSourceData>,
+
+    /// Data associated with devicetree binding schemas
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "b", default, skip_serializing_if = "Map::is_empty")
+    )]
+    pub bindings: Map<PathBuf, BindingData>,
+
+    /// Sections of MAINTAINERS file
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "m", default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub maintainers: Vec<MaintainersData>,
+
+    /// Data associated with kernel modules
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "k", default, skip_serializing_if = "Map::is_empty")
+    )]
+    pub kmods: Map<PathBuf, KmodData>,
+
+    /// Modification stamps of indexed sources, headers and kbuild files
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "t", default, skip_serializing_if = "Map::is_empty")
+    )]
+    pub stamps: Map<PathBuf, FileStamp>,
+
+    /// Parsed statements of kbuild files
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "f", default, skip_serializing_if = "Map::is_empty")
+    )]
+    pub(crate) makefiles: Map<PathBuf, Vec<MakeStmt>>,
+
+    /// Directories of merged external modules by path prefix
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "x", default, skip_serializing_if = "Map::is_empty")
+    )]
+    pub externals: Map<PathBuf, PathBuf>,
 }
 
 impl MetaData {
+    /// Current format version of index
+    ///
+    /// Should be increased on incompatible changes of serialized data.
+    pub const FORMAT_VERSION: u32 = 3;
+
     /// Get reference to source data by path
     pub fn source(&self, source: impl AsRef<Path>) -> Option<&SourceData> {
         self.sources.get(source.as_ref())
@@ -169,6 +736,286 @@ impl MetaData {
         self.compat_strs.get_mut(compat_str).unwrap()
     }
 
+    /// Get reference to device data by bus and identifier
+    pub fn device(&self, bus: DeviceBus, id: impl AsRef<str>) -> Option<&DeviceData> {
+        self.devices.get(&bus)?.get(id.as_ref())
+    }
+
+    /// Get mutable reference to device data by bus and identifier
+    pub fn device_mut(&mut self, bus: DeviceBus, id: impl AsRef<str>) -> &mut DeviceData {
+        let id = id.as_ref();
+        let devices = self.devices.entry(bus).or_default();
+
+        if !devices.contains_key(id) {
+            devices.insert(id.into(), Default::default());
+        }
+
+        devices.get_mut(id).unwrap()
+    }
+
+    /// Find devices which matches identifier (taking wildcards into account)
+    pub fn find_devices<'a>(
+        &'a self,
+        bus: DeviceBus,
+        id: &str,
+    ) -> impl Iterator<Item = (&'a String, &'a DeviceData)> + 'a {
+        let id = id.to_owned();
+
+        self.devices
+            .get(&bus)
+            .into_iter()
+            .flatten()
+            .filter(move |(pattern, _)| bus.id_matches(pattern, &id))
+    }
+
+    /// Get reference to symbol data by name
+    pub fn symbol(&self, symbol: impl AsRef<str>) -> Option<&SymbolData> {
+        self.symbols.get(symbol.as_ref())
+    }
+
+    /// Get mutable reference to symbol data by name
+    pub fn symbol_mut(&mut self, symbol: impl AsRef<str>) -> &mut SymbolData {
+        let symbol = symbol.as_ref();
+
+        if !self.symbols.contains_key(symbol) {
+            self.symbols.insert(symbol.into(), Default::default());
+        }
+
+        self.symbols.get_mut(symbol).unwrap()
+    }
+
+    /// Get exports of symbol with exporting sources
+    pub fn symbol_exports<'a>(
+        &'a self,
+        symbol: &'a str,
+    ) -> impl Iterator<Item = (&'a PathBuf, &'a SourceData, &'a ExportData)> + 'a {
+        self.symbol(symbol)
+            .into_iter()
+            .flat_map(|symbol_data| &symbol_data.exported_by)
+            .filter_map(move |source| {
+                let source_data = self.source(source)?;
+                let export_data = source_data.exports.get(symbol)?;
+                Some((source, source_data, export_data))
+            })
+    }
+
+    /// Get module path and data by module name (like `arm_smmu`) or path
+    ///
+    /// Dashes and underscores in module names are interchangeable like in `modprobe`.
+    pub fn kmod(&self, kmod: impl AsRef<str>) -> Option<(&PathBuf, &KmodData)> {
+        let kmod = kmod.as_ref();
+
+        if let Some(entry) = self.kmods.get_key_value(Path::new(kmod)) {
+            return Some(entry);
+        }
+
+        let name = KmodData::name_of(kmod.trim_end_matches(".ko"));
+
+        self.kmods
+            .iter()
+            .find(|(path, _)| KmodData::name_of(path.to_string_lossy()) == name)
+    }
+
+    /// Get mutable reference to module data by path
+    pub fn kmod_mut(&mut self, kmod: impl AsRef<Path>) -> &mut KmodData {
+        let kmod = kmod.as_ref();
+
+        if !self.kmods.contains_key(kmod) {
+            self.kmods.insert(kmod.into(), Default::default());
+        }
+
+        self.kmods.get_mut(kmod).unwrap()
+    }
+
+    /// Get module data merged from all member sources of module
+    pub fn kmod_module(&self, kmod: impl AsRef<Path>) -> ModuleData {
+        let mut module = ModuleData::default();
+
+        if let Some(kmod_data) = self.kmods.get(kmod.as_ref()) {
+            for source in &kmod_data.sources {
+                if let Some(source_module) =
+                    self.source(source).and_then(|data| data.module.as_ref())
+                {
+                    module.merge(source_module, source);
+                }
+            }
+        }
+
+        module
+    }
+
+    /// Get module data of source merged with other member sources of same module
+    ///
+    /// Source linked into several modules gets data of all those modules merged,
+    /// conflicting licenses are reported. Source module data is returned as is
+    /// when source does not belong to any module.
+    pub fn source_module(&self, source: impl AsRef<Path>) -> Option<ModuleData> {
+        let source = source.as_ref();
+        let mut kmods = self.kmods_of_source(source).peekable();
+
+        if kmods.peek().is_none() {
+            return self.source(source)?.module.clone();
+        }
+
+        let mut module = ModuleData::default();
+
+        for (kmod, _) in kmods {
+            let kmod_module = self.kmod_module(kmod);
+
+            if !module.license.is_empty()
+                && !kmod_module.license.is_empty()
+                && module.license != kmod_module.license
+            {
+                log::warn!(
+                    "Source {} linked into modules with different licenses: \"{}\" and \"{}\" ({})",
+                    source.display(),
+                    module.license,
+                    kmod_module.license,
+                    kmod.display(),
+                );
+            }
+
+            module.merge(&kmod_module, kmod);
+        }
+
+        Some(module).filter(|module| !module.is_empty())
+    }
+
+    /// Get parameters of all modules by full name (like `arm_smmu.disable_bypass`)
+    ///
+    /// Parameters of built-in code are prefixed by name of source file like `KBUILD_MODNAME`.
+    pub fn module_params(&self) -> Map<String, (&PathBuf, &ParamData)> {
+        let mut params = Map::default();
+
+        for (source, source_data) in &self.sources {
+            let module = match &source_data.module {
+                Some(module) if !module.params.is_empty() => module,
+                _ => continue,
+            };
+            let prefix = if let Some((kmod, _)) = self.kmods_of_source(source).next() {
+                KmodData::name_of(kmod.to_string_lossy())
+            } else if !module.name.is_empty() {
+                module.name.clone()
+            } else {
+                KmodData::name_of(source.with_extension("").to_string_lossy())
+            };
+            for (name, param) in &module.params {
+                params.insert(format!("{}.{}", prefix, name), (source, param));
+            }
+        }
+
+        params
+    }
+
+    /// Get modules which includes source file
+    pub fn kmods_of_source(
+        &self,
+        source: impl AsRef<Path>,
+    ) -> impl Iterator<Item = (&PathBuf, &KmodData)> {
+        let source = source.as_ref().to_path_buf();
+
+        self.kmods
+            .iter()
+            .filter(move |(_, kmod_data)| kmod_data.sources.contains(&source))
+    }
+
+    /// Get reference to header data by path
+    pub fn header(&self, header: impl AsRef<Path>) -> Option<&HeaderData> {
+        self.headers.get(header.as_ref())
+    }
+
+    /// Get mutable reference to header data by path
+    pub fn header_mut(&mut self, header: impl AsRef<Path>) -> &mut HeaderData {
+        let header = header.as_ref();
+
+        if !self.headers.contains_key(header) {
+            self.headers.insert(header.into(), Default::default());
+        }
+
+        self.headers.get_mut(header).unwrap()
+    }
+
+    /// Get reference to devicetree source data by path
+    pub fn dt_source(&self, dt_source: impl AsRef<Path>) -> Option<&DtSourceData> {
+        self.dt_sources.get(dt_source.as_ref())
+    }
+
+    /// Get reference to binding schema data by path
+    pub fn binding(&self, binding: impl AsRef<Path>) -> Option<&BindingData> {
+        self.bindings.get(binding.as_ref())
+    }
+
+    /// Get compatible strings used by drivers which has no binding schemas
+    pub fn compat_strs_without_bindings(&self) -> impl Iterator<Item = &String> {
+        self.compat_strs
+            .iter()
+            .filter(|(_, compat_data)| {
+                !compat_data.sources.is_empty() && compat_data.bindings.is_empty()
+            })
+            .map(|(compat, _)| compat)
+    }
+
+    /// Get binding schemas which compatible strings has no drivers
+    pub fn bindings_without_drivers(&self) -> impl Iterator<Item = (&PathBuf, &BindingData)> {
+        self.bindings.iter().filter(|(_, binding_data)| {
+            binding_data
+                .compat_strs
+                .iter()
+                .all(|compat| self.compat_str_sources(compat).is_empty())
+        })
+    }
+
+    /// Get sources which implements drivers for compatible string
+    pub fn compat_str_sources(&self, compat_str: impl AsRef<str>) -> Set<&PathBuf> {
+        let compat_str = compat_str.as_ref();
+
+        self.compat_str(compat_str)
+            .map(|compat_data| &compat_data.sources)
+            .into_iter()
+            .chain(
+                self.device(DeviceBus::Of, compat_str)
+                    .map(|device_data| &device_data.sources),
+            )
+            .flatten()
+            .collect()
+    }
+
+    /// Find header by path or by include name (like `linux/iommu.h`)
+    pub fn find_header(&self, header: impl AsRef<Path>) -> Option<(&PathBuf, &HeaderData)> {
+        let header = header.as_ref();
+
+        ["", "include", "include/uapi"]
+            .into_iter()
+            .find_map(|dir| self.headers.get_key_value(&Path::new(dir).join(header)))
+    }
+
+    /// Get files included by source or header file
+    pub fn includes_of(&self, path: impl AsRef<Path>) -> Option<&Set<PathBuf>> {
+        let path = path.as_ref();
+
+        self.source(path)
+            .map(|source_data| &source_data.includes)
+            .or_else(|| self.header(path).map(|header_data| &header_data.includes))
+    }
+
+    /// Get all sources and headers which includes header file directly or indirectly
+    pub fn included_by_all(&self, header: impl AsRef<Path>) -> Set<&PathBuf> {
+        let mut result = Set::default();
+        let mut queue = vec![header.as_ref()];
+
+        while let Some(header) = queue.pop() {
+            if let Some(header_data) = self.header(header) {
+                for path in &header_data.included_by {
+                    if result.insert(path) {
+                        queue.push(path);
+                    }
+                }
+            }
+        }
+
+        result
+    }
+
     /// Synchronize all data with sources data
     pub fn sync_with_sources(&mut self) {
         let mut this = Self::default();
@@ -178,20 +1025,125 @@ impl MetaData {
             SourceData {
                 config_opts,
                 compat_strs,
+                exports,
+                symbol_refs,
+                device_tables,
                 ..
             },
         ) in &self.sources
         {
             for config_opt in config_opts {
-                this.config_opt_mut(config_opt).add_source(&source);
+                this.config_opt_mut(config_opt).add_source(source);
             }
             for compat_str in compat_strs {
-                this.compat_str_mut(compat_str).set_source(&source);
+                this.compat_str_mut(compat_str).add_source(source);
+            }
+            for symbol in exports.keys() {
+                this.symbol_mut(symbol).exported_by.insert(source.clone());
+            }
+            for symbol in symbol_refs {
+                this.symbol_mut(symbol).used_by.insert(source.clone());
+            }
+            for table in device_tables.values() {
+                for entry in &table.entries {
+                    this.device_mut(table.bus, &entry.id).add_source(source);
+                }
+            }
+        }
+
+        for (binding, binding_data) in &self.bindings {
+            for compat_str in &binding_data.compat_strs {
+                this.compat_str_mut(compat_str).add_binding(binding);
             }
         }
 
+        // keep configuration options defined in Kconfig files
+        for (name, config_opt) in core::mem::take(&mut self.config_opts) {
+            if config_opt.is_defined() {
+                let sources = this
+                    .config_opts
+                    .remove(&name)
+                    .map(|data| data.sources)
+                    .unwrap_or_default();
+                this.config_opts.insert(
+                    name,
+                    ConfigOptData {
+                        sources,
+                        ..config_opt
+                    },
+                );
+            }
+        }
+
+        // fill reverse dependencies
+        let selects = this
+            .config_opts
+            .iter()
+            .flat_map(|(name, data)| {
+                data.selects
+                    .keys()
+                    .map(move |selected| (selected.clone(), name.clone()))
+            })
+            .collect::<Vec<_>>();
+
+        for (selected, name) in selects {
+            this.config_opt_mut(selected).selected_by.insert(name);
+        }
+
         self.config_opts = this.config_opts;
         self.compat_strs = this.compat_strs;
+        self.devices = this.devices;
+        self.symbols = this.symbols;
+
+        // drop objects which are not modules (like single-source `obj-y` objects)
+        self.kmods
+            .retain(|_, kmod_data| !kmod_data.sources.is_empty());
+
+        // fill reverse include relations
+        let includes = self
+            .sources
+            .iter()
+            .map(|(path, source_data)| (path, &source_data.includes))
+            .chain(
+                self.headers
+                    .iter()
+                    .map(|(path, header_data)| (path, &header_data.includes)),
+            )
+            .flat_map(|(path, includes)| {
+                includes
+                    .iter()
+                    .map(move |include| (include.clone(), path.clone()))
+            })
+            .collect::<Vec<_>>();
+
+        for header_data in self.headers.values_mut() {
+            header_data.included_by.clear();
+        }
+
+        for (header, path) in includes {
+            self.header_mut(header).included_by.insert(path);
+        }
+    }
+
+    /// Get all configuration options which given option requires (transitively)
+    ///
+    /// Alternatives (`A || B`) and negations (`!A`) in dependencies is not treated as required.
+    pub fn config_opt_requires(&self, config_opt: impl AsRef<str>) -> Set<String> {
+        let config_opt = config_opt.as_ref();
+        let mut requires = Set::default();
+        let mut queue = vec![config_opt];
+
+        while let Some(name) = queue.pop() {
+            if let Some(data) = self.config_opt(name) {
+                for dep in data.depends_on.required_symbols() {
+                    if dep != config_opt && requires.insert(dep.to_string()) {
+                        queue.push(dep);
+                    }
+                }
+            }
+        }
+
+        requires
     }
 }
 
@@ -201,6 +1153,20 @@ impl SourceData {
         self.config_opts.extend(config_opts.into());
     }
 
+    /// Add build condition to source data
+    ///
+    /// Configuration options which should be enabled will be added too.
+    pub fn add_condition(&mut self, condition: Condition) {
+        self.add_config_opts(
+            condition
+                .positive_symbols()
+                .into_iter()
+                .map(String::from)
+                .collect::<Set<_>>(),
+        );
+        self.condition &= condition;
+    }
+
     /// Add associated compatible strings to source data
     pub fn add_compat_strs(&mut self, compat_strs: impl Into<Set<String>>) {
         self.compat_strs.extend(compat_strs.into());
@@ -210,11 +1176,50 @@ impl SourceData {
 impl ModuleData {
     /// Check that all module fields is empty
     pub fn is_empty(&self) -> bool {
-        self.authors.is_empty()
+        self.name.is_empty()
+            && self.authors.is_empty()
             && self.description.is_empty()
             && self.license.is_empty()
             && self.aliases.is_empty()
             && self.params.is_empty()
+            && self.imports.is_empty()
+    }
+
+    /// Merge module data of another member source of same module
+    ///
+    /// Lists are joined without duplicates, parameters remember defining source file
+    /// (given source is used when parameter has no one).
+    pub fn merge(&mut self, other: &Self, source: impl AsRef<Path>) {
+        if self.name.is_empty() {
+            self.name = other.name.clone();
+        }
+        for author in &other.authors {
+            if !self.authors.contains(author) {
+                self.authors.push(author.clone());
+            }
+        }
+        if self.description.is_empty() {
+            self.description = other.description.clone();
+        }
+        if self.license.is_empty() {
+            self.license = other.license.clone();
+        }
+        for alias in &other.aliases {
+            if !self.aliases.contains(alias) {
+                self.aliases.push(alias.clone());
+            }
+        }
+        for (name, param) in &other.params {
+            let param = ParamData {
+                source: param
+                    .source
+                    .clone()
+                    .or_else(|| Some(source.as_ref().into())),
+                ..param.clone()
+            };
+            self.params.entry(name.clone()).or_insert(param);
+        }
+        self.imports.extend(other.imports.iter().cloned());
     }
 }
 
@@ -223,11 +1228,205 @@ impl ConfigOptData {
     pub fn add_source(&mut self, source: impl Into<PathBuf>) {
         self.sources.insert(source.into());
     }
+
+    /// Check that configuration option is defined in Kconfig files
+    pub fn is_defined(&self) -> bool {
+        !self.locations.is_empty()
+    }
+}
+
+impl DeviceBus {
+    /// Determine device bus by name of device id structure
+    pub fn from_struct(name: impl AsRef<str>) -> Option<Self> {
+        Some(match name.as_ref() {
+            "of_device_id" => Self::Of,
+            "acpi_device_id" => Self::Acpi,
+            "pci_device_id" => Self::Pci,
+            "usb_device_id" => Self::Usb,
+            "i2c_device_id" => Self::I2c,
+            "spi_device_id" => Self::Spi,
+            "platform_device_id" => Self::Platform,
+            _ => return None,
+        })
+    }
+
+    /// Check that device identifier matches identifier from match table
+    ///
+    /// Components of PCI and USB identifiers may be wildcards (`*`)
+    /// or have masked out digits (`?`).
+    pub fn id_matches(&self, pattern: impl AsRef<str>, id: impl AsRef<str>) -> bool {
+        let (pattern, id) = (pattern.as_ref(), id.as_ref());
+
+        match self {
+            Self::Of => pattern == id,
+            Self::Pci | Self::Usb => {
+                let pattern = pattern.split(':').collect::<Vec<_>>();
+                let id = id.split(':').collect::<Vec<_>>();
+
+                pattern.len() <= id.len()
+                    && pattern.iter().zip(&id).all(|(pattern, id)| {
+                        *pattern == "*"
+                            || pattern.len() == id.len()
+                                && pattern.chars().zip(id.chars()).all(|(pattern, id)| {
+                                    pattern == '?' || pattern.eq_ignore_ascii_case(&id)
+                                })
+                    })
+            }
+            _ => pattern.eq_ignore_ascii_case(id),
+        }
+    }
+}
+
+impl AsRef<str> for DeviceBus {
+    fn as_ref(&self) -> &str {
+        match self {
+            Self::Of => "of",
+            Self::Acpi => "acpi",
+            Self::Pci => "pci",
+            Self::Usb => "usb",
+            Self::I2c => "i2c",
+            Self::Spi => "spi",
+            Self::Platform => "platform",
+        }
+    }
+}
+
+impl core::fmt::Display for DeviceBus {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str(self.as_ref())
+    }
+}
+
+impl SourceLang {
+    /// Source file extensions
+    pub const EXTENSIONS: &'static [&'static str] = &["c", "S", "rs"];
+
+    /// Determine source language by file extension
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        Some(match path.as_ref().extension()?.to_str()? {
+            "c" => Self::C,
+            "S" => Self::Asm,
+            "rs" => Self::Rust,
+            _ => return None,
+        })
+    }
+
+    /// Check that source language is C
+    pub fn is_c(&self) -> bool {
+        *self == Self::C
+    }
+}
+
+impl AsRef<str> for SourceLang {
+    fn as_ref(&self) -> &str {
+        match self {
+            Self::C => "c",
+            Self::Asm => "asm",
+            Self::Rust => "rust",
+        }
+    }
+}
+
+impl core::fmt::Display for SourceLang {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str(self.as_ref())
+    }
+}
+
+impl core::fmt::Display for SourceLoc {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{}:{}", self.file.display(), self.line)
+    }
+}
+
+impl AsRef<str> for ConfigOptType {
+    fn as_ref(&self) -> &str {
+        match self {
+            Self::Bool => "bool",
+            Self::Tristate => "tristate",
+            Self::Int => "int",
+            Self::Hex => "hex",
+            Self::String => "string",
+        }
+    }
+}
+
+impl core::fmt::Display for ConfigOptType {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str(self.as_ref())
+    }
+}
+
+impl core::str::FromStr for ConfigOptType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "bool" | "boolean" | "def_bool" => Self::Bool,
+            "tristate" | "def_tristate" => Self::Tristate,
+            "int" => Self::Int,
+            "hex" => Self::Hex,
+            "string" => Self::String,
+            _ => anyhow::bail!("Unsupported configuration option type: {}", s),
+        })
+    }
 }
 
 impl CompatStrData {
-    /// Set associated source to compatible string data
-    pub fn set_source(&mut self, source: impl Into<PathBuf>) {
-        self.source = source.into();
+    /// Add associated source to compatible string data
+    pub fn add_source(&mut self, source: impl Into<PathBuf>) {
+        self.sources.insert(source.into());
+    }
+
+    /// Add associated binding schema to compatible string data
+    pub fn add_binding(&mut self, binding: impl Into<PathBuf>) {
+        self.bindings.insert(binding.into());
+    }
+}
+
+impl FileStamp {
+    /// Check that file was not changed using modification time and size
+    ///
+    /// Contents hash should be compared when it returns `false`
+    /// because modification time may change without changing contents (like after `git checkout`).
+    pub fn same_meta(&self, mtime: u64, size: u64) -> bool {
+        self.mtime == mtime && self.size == size
+    }
+
+    /// Calculate FNV-1a hash of contents
+    pub fn hash_of(data: &[u8]) -> u64 {
+        Self::hash_update(0xcbf29ce484222325, data)
+    }
+
+    /// Update FNV-1a hash using next part of contents
+    pub fn hash_update(hash: u64, data: &[u8]) -> u64 {
+        data.iter().fold(hash, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+        })
+    }
+}
+
+impl KmodData {
+    /// Name of kernel image which contains built-in code
+    pub const VMLINUX: &'static str = "vmlinux";
+
+    /// Get module name from path (like `arm_smmu` for `drivers/iommu/arm/arm-smmu/arm-smmu.ko`)
+    pub fn name_of(path: impl AsRef<str>) -> String {
+        let path = path.as_ref();
+        let name = path.rsplit('/').next().unwrap_or(path);
+
+        name.trim_end_matches(".ko").replace('-', "_")
+    }
+
+    /// Add source file linked into module
+    pub fn add_source(&mut self, source: impl Into<PathBuf>) {
+        self.sources.insert(source.into());
+    }
+}
+
+impl DeviceData {
+    /// Add associated source to device data
+    pub fn add_source(&mut self, source: impl Into<PathBuf>) {
+        self.sources.insert(source.into());
     }
 }
@@ -0,0 +1,384 @@
+use crate::{DeviceBus, MetaData, ModuleData, PathBuf};
+use std::collections::BTreeSet as Set;
+
+impl MetaData {
+    /// Find sources of drivers which matches modalias (like `of:NfooT(null)Carm,smmu-v2`)
+    ///
+    /// Device match tables and module aliases are taken into account.
+    pub fn resolve_modalias(&self, modalias: impl AsRef<str>) -> Set<&PathBuf> {
+        let modalias = modalias.as_ref().trim();
+        let mut sources = Set::default();
+
+        for (bus, id) in self.modalias_device_ids(modalias) {
+            for (_, device_data) in self.find_devices(bus, &id) {
+                sources.extend(&device_data.sources);
+            }
+            if bus == DeviceBus::Of {
+                if let Some(compat_data) = self.compat_str(&id) {
+                    sources.extend(&compat_data.sources);
+                }
+            }
+        }
+
+        let matches = |module: &ModuleData| {
+            module
+                .aliases
+                .iter()
+                .any(|alias| wildcard_match(alias, modalias))
+        };
+
+        // aliases declared by any source of module applies to all its sources
+        for (kmod, kmod_data) in &self.kmods {
+            if matches(&self.kmod_module(kmod)) {
+                sources.extend(&kmod_data.sources);
+            }
+        }
+
+        for (source, source_data) in &self.sources {
+            if let Some(module) = &source_data.module {
+                if matches(module) && self.kmods_of_source(source).next().is_none() {
+                    sources.insert(source);
+                }
+            }
+        }
+
+        sources
+    }
+
+    /// Extract device identifiers from modalias
+    fn modalias_device_ids(&self, modalias: &str) -> Vec<(DeviceBus, String)> {
+        let (prefix, alias) = match modalias.split_once(':') {
+            Some(parts) => parts,
+            None => return Vec::default(),
+        };
+
+        match prefix {
+            // of:N<name>T<type>C<compatible>C<compatible>...
+            "of" => {
+                let compats = match alias.find('T').and_then(|pos| {
+                    let rest = &alias[pos..];
+                    rest.find('C').map(|pos| &rest[pos..])
+                }) {
+                    Some(compats) => compats,
+                    None => return Vec::default(),
+                };
+
+                // compatible strings may contain `C` so check all possible splits
+                let starts = compats
+                    .match_indices('C')
+                    .map(|(pos, _)| pos)
+                    .collect::<Vec<_>>();
+                let mut ids = Vec::default();
+
+                for (index, start) in starts.iter().enumerate() {
+                    for end in starts[index + 1..]
+                        .iter()
+                        .copied()
+                        .chain(core::iter::once(compats.len()))
+                    {
+                        // spaces in compatible strings replaced by underscores
+                        let compat = &compats[start + 1..end];
+                        if let Some(compat) = [compat.to_string(), compat.replace('_', " ")]
+                            .into_iter()
+                            .find(|compat| {
+                                self.compat_str(compat).is_some()
+                                    || self.device(DeviceBus::Of, compat).is_some()
+                            })
+                        {
+                            ids.push((DeviceBus::Of, compat));
+                        }
+                    }
+                }
+
+                ids
+            }
+            // pci:v<vendor>d<device>sv<subvendor>sd<subdevice>bc<class>sc<subclass>i<interface>
+            "pci" => {
+                let mut fields = HexFields(alias);
+                let vendor = fields.next("v", 8);
+                let device = fields.next("d", 8);
+                let _ = (fields.next("sv", 8), fields.next("sd", 8));
+                let class = [
+                    fields.next("bc", 2),
+                    fields.next("sc", 2),
+                    fields.next("i", 2),
+                ];
+
+                match (vendor, device) {
+                    (Some(vendor), Some(device)) => {
+                        let mut id = format!("{}:{}", &vendor[4..], &device[4..]);
+                        if let [Some(base), Some(sub), Some(iface)] = class {
+                            id.push_str(&format!(":{}{}{}", base, sub, iface));
+                        }
+                        vec![(DeviceBus::Pci, id)]
+                    }
+                    _ => Vec::default(),
+                }
+            }
+            // usb:v<vendor>p<product>d<release>dc<class>dsc<subclass>dp<protocol>ic<ifclass>isc<ifsubclass>ip<ifprotocol>in<ifnumber>
+            "usb" => {
+                let mut fields = HexFields(alias);
+
+                match (fields.next("v", 4), fields.next("p", 4)) {
+                    (Some(vendor), Some(product)) => {
+                        let mut id = format!("{}:{}", vendor, product);
+                        let _ = fields.next("d", 4);
+                        for prefix in ["dc", "dsc", "dp", "ic", "isc", "ip"] {
+                            match fields.next(prefix, 2) {
+                                Some(value) => {
+                                    id.push(':');
+                                    id.push_str(&value);
+                                }
+                                None => break,
+                            }
+                        }
+                        vec![(DeviceBus::Usb, id)]
+                    }
+                    _ => Vec::default(),
+                }
+            }
+            // acpi:<id>:<id>:...
+            "acpi" => alias
+                .split(':')
+                .filter(|id| !id.is_empty())
+                .map(|id| (DeviceBus::Acpi, id.into()))
+                .collect(),
+            "i2c" => vec![(DeviceBus::I2c, alias.into())],
+            "spi" => vec![(DeviceBus::Spi, alias.into())],
+            "platform" => vec![(DeviceBus::Platform, alias.into())],
+            _ => Vec::default(),
+        }
+    }
+}
+
+/// Sequence of prefixed fixed-width hexadecimal fields
+struct HexFields<'s>(&'s str);
+
+impl<'s> HexFields<'s> {
+    /// Get next field value in lowercase
+    ///
+    /// Wildcard field (like `sv*` in module aliases) is skipped.
+    fn next(&mut self, prefix: &str, width: usize) -> Option<String> {
+        let rest = self.0.strip_prefix(prefix)?;
+
+        if let Some(rest) = rest.strip_prefix('*') {
+            self.0 = rest;
+            return None;
+        }
+
+        let value = rest.get(..width)?;
+
+        if !value.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+
+        self.0 = &self.0[prefix.len() + width..];
+
+        Some(value.to_ascii_lowercase())
+    }
+}
+
+/// Match string using shell-like wildcard pattern (`*`, `?` and `[...]`)
+pub(crate) fn wildcard_match(pattern: &str, string: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let string = string.chars().collect::<Vec<_>>();
+
+    // position in pattern and string to restart from after last `*`
+    let mut restart = None;
+    let (mut p, mut s) = (0, 0);
+
+    while s < string.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                restart = Some((p, s));
+                p += 1;
+                continue;
+            }
+            Some('?') => {
+                p += 1;
+                s += 1;
+                continue;
+            }
+            Some('[') => {
+                if let Some(len) = pattern[p + 1..].iter().position(|c| *c == ']') {
+                    let class = &pattern[p + 1..p + 1 + len];
+                    let (negated, class) = match class.first() {
+                        Some('!' | '^') => (true, &class[1..]),
+                        _ => (false, class),
+                    };
+                    let mut matched = false;
+                    let mut index = 0;
+                    while index < class.len() {
+                        if index + 2 < class.len() && class[index + 1] == '-' {
+                            matched |= (class[index]..=class[index + 2]).contains(&string[s]);
+                            index += 3;
+                        } else {
+                            matched |= class[index] == string[s];
+                            index += 1;
+                        }
+                    }
+                    if matched != negated {
+                        p += len + 2;
+                        s += 1;
+                        continue;
+                    }
+                } else if string[s] == '[' {
+                    p += 1;
+                    s += 1;
+                    continue;
+                }
+            }
+            Some(c) if *c == string[s] => {
+                p += 1;
+                s += 1;
+                continue;
+            }
+            _ => {}
+        }
+
+        // mismatch: let last `*` consume one more character
+        match restart {
+            Some((star, pos)) => {
+                restart = Some((star, pos + 1));
+                p = star + 1;
+                s = pos + 1;
+            }
+            None => return false,
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::DeviceTableData;
+
+    #[test]
+    fn wildcards() {
+        assert!(wildcard_match(
+            "of:N*T*Cvendor,chip",
+            "of:NfooT(null)Cvendor,chip"
+        ));
+        assert!(wildcard_match(
+            "of:N*T*Cvendor,chipC*",
+            "of:NfooT(null)Cvendor,chipC"
+        ));
+        assert!(!wildcard_match(
+            "of:N*T*Cvendor,chip",
+            "of:NfooT(null)Cvendor,chip2"
+        ));
+        assert!(wildcard_match("i2c:ad?414", "i2c:ad7414"));
+        assert!(wildcard_match(
+            "pci:v*d*bc0[0-3]*",
+            "pci:v00008086d00001234bc01sc08i02"
+        ));
+        assert!(!wildcard_match(
+            "pci:v*d*bc0[!0-3]*",
+            "pci:v00008086d00001234bc01sc08i02"
+        ));
+        assert!(wildcard_match("acpi*:PNP0C0[9A]:*", "acpi:PNP0C0A:"));
+        assert!(wildcard_match("platform:*", "platform:"));
+        assert!(!wildcard_match("", "platform:foo"));
+    }
+
+    #[test]
+    fn of_compatibles() {
+        let mut metadata = MetaData::default();
+        metadata.source_mut("chip.c").compat_strs.extend([
+            "vendor,chip".into(),
+            "vendor,CChip".into(),
+            "vendor,chip v2".into(),
+        ]);
+        metadata.sync_with_sources();
+
+        let ids = |modalias| {
+            metadata
+                .modalias_device_ids(modalias)
+                .into_iter()
+                .map(|(bus, id)| {
+                    assert_eq!(bus, DeviceBus::Of);
+                    id
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(ids("of:NfooT(null)Cvendor,chip"), ["vendor,chip"]);
+        assert_eq!(
+            ids("of:NfooT(null)Cvendor,CChipCvendor,chip"),
+            ["vendor,CChip", "vendor,chip"]
+        );
+        assert_eq!(ids("of:NfooT(null)Cvendor,chip_v2"), ["vendor,chip v2"]);
+        assert!(ids("of:NfooT(null)Cvendor,unknown").is_empty());
+        assert!(ids("of:Nfoo").is_empty());
+    }
+
+    #[test]
+    fn hex_fields() {
+        let metadata = MetaData::default();
+
+        assert_eq!(
+            metadata.modalias_device_ids("pci:v00008086d00001234sv*sd*bc01sc08i02"),
+            [(DeviceBus::Pci, "8086:1234:010802".into())]
+        );
+        assert_eq!(
+            metadata.modalias_device_ids("pci:v00008086d00001234sv00001028sd000004DAbc0Csc03i30"),
+            [(DeviceBus::Pci, "8086:1234:0c0330".into())]
+        );
+        assert_eq!(
+            metadata.modalias_device_ids("pci:v00008086d00001234"),
+            [(DeviceBus::Pci, "8086:1234".into())]
+        );
+        assert_eq!(
+            metadata.modalias_device_ids("usb:v046DpC52Bd1211dc00dsc00dp00ic03isc01ip02in00"),
+            [(DeviceBus::Usb, "046d:c52b:00:00:00:03:01:02".into())]
+        );
+        assert_eq!(
+            metadata.modalias_device_ids("usb:v046DpC52Bd*"),
+            [(DeviceBus::Usb, "046d:c52b".into())]
+        );
+        assert!(metadata.modalias_device_ids("pci:v8086d1234").is_empty());
+
+        let mut fields = HexFields("v0000ABCDsv*d12");
+        assert_eq!(fields.next("v", 8).as_deref(), Some("0000abcd"));
+        assert_eq!(fields.next("sv", 8), None);
+        assert_eq!(fields.next("d", 2).as_deref(), Some("12"));
+        assert_eq!(fields.next("i", 2), None);
+    }
+
+    #[test]
+    fn device_tables_and_aliases() {
+        let mut metadata = MetaData::default();
+        metadata.source_mut("nvme.c").device_tables = DeviceTableData::from_source(
+            "static const struct pci_device_id nvme_id_table[] = {\n\
+             \t{ PCI_DEVICE_CLASS(0x010802, 0xffffff) },\n\
+             \t{ 0, }\n\
+             };\n",
+        );
+        metadata.source_mut("foo.c").module = Some(ModuleData {
+            aliases: ["platform:foo-*".into()].into(),
+            ..Default::default()
+        });
+        for source in ["foo.c", "foo-core.c"] {
+            metadata.kmod_mut("foo.ko").add_source(source);
+        }
+        metadata.sync_with_sources();
+
+        let sources = |modalias| {
+            metadata
+                .resolve_modalias(modalias)
+                .into_iter()
+                .map(|source| source.to_str().unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            sources("pci:v0000144Dd0000A808sv0000144Dsd0000A801bc01sc08i02"),
+            ["nvme.c"]
+        );
+        assert!(sources("pci:v0000144Dd0000A808sv0000144Dsd0000A801bc01sc06i01").is_empty());
+        // aliases of module applies to all its sources
+        assert_eq!(sources("platform:foo-bar"), ["foo-core.c", "foo.c"]);
+    }
+}
@@ -0,0 +1,218 @@
+use crate::{ModuleData, ParamData};
+
+use clex::{Lexer, Token};
+
+/// Token slice with string value (for string literals)
+type Tok<'s> = (&'s str, Option<String>);
+
+impl ModuleData {
+    /// Extract module data from `module!` macro of Rust source
+    pub fn from_rust_source(src: &str) -> Option<Self> {
+        let start = src.match_indices("module!").find_map(|(idx, _)| {
+            // skip identifiers which ends with "module"
+            let prev = src[..idx].chars().next_back();
+            if prev
+                .map(|c| c.is_alphanumeric() || c == '_')
+                .unwrap_or(false)
+            {
+                None
+            } else {
+                Some(idx)
+            }
+        })?;
+
+        let tokens = Lexer::from(&src[start + "module!".len()..])
+            .filter(|lexeme| lexeme.token != Token::Comment)
+            .map(|lexeme| {
+                let string = if lexeme.token == Token::String {
+                    lexeme.string()
+                } else {
+                    None
+                };
+                (lexeme.slice, string)
+            })
+            .collect::<Vec<_>>();
+
+        let fields = block_tokens(&tokens)?;
+
+        let mut module = ModuleData::default();
+
+        for (key, value) in split_fields(fields) {
+            match key {
+                "name" => module.name = strings(value).next().unwrap_or_default(),
+                "author" | "authors" => module.authors.extend(strings(value)),
+                "description" => module.description = strings(value).next().unwrap_or_default(),
+                "license" => module.license = strings(value).next().unwrap_or_default(),
+                "alias" | "aliases" => module.aliases.extend(strings(value)),
+                "params" => {
+                    if let Some(params) = block_tokens(value) {
+                        for (name, value) in split_fields(params) {
+                            // name: type { default: ..., permissions: ..., description: ... }
+                            let type_end = value
+                                .iter()
+                                .position(|(slice, _)| *slice == "{")
+                                .unwrap_or(value.len());
+                            let mut param = ParamData {
+                                type_: value[..type_end].iter().map(|(slice, _)| *slice).collect(),
+                                ..Default::default()
+                            };
+                            if let Some(attrs) = block_tokens(&value[type_end..]) {
+                                for (key, value) in split_fields(attrs) {
+                                    match key {
+                                        "permissions" => {
+                                            param.perm = parse_int(value).unwrap_or_default()
+                                        }
+                                        "description" => {
+                                            param.description =
+                                                strings(value).next().unwrap_or_default()
+                                        }
+                                        _ => {}
+                                    }
+                                }
+                            }
+                            module.params.insert(name.into(), param);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if module.is_empty() {
+            None
+        } else {
+            Some(module)
+        }
+    }
+}
+
+/// Get tokens inside of block which starts with first token
+fn block_tokens<'t, 's>(tokens: &'t [Tok<'s>]) -> Option<&'t [Tok<'s>]> {
+    if !matches!(tokens.first(), Some(("{", _))) {
+        return None;
+    }
+
+    let mut depth = 0usize;
+
+    for (idx, (slice, _)) in tokens.iter().enumerate() {
+        match *slice {
+            "{" | "[" | "(" => depth += 1,
+            "}" | "]" | ")" => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(&tokens[1..idx]);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Split tokens into `key: value` fields separated by commas
+fn split_fields<'t, 's>(tokens: &'t [Tok<'s>]) -> Vec<(&'s str, &'t [Tok<'s>])> {
+    let mut fields = Vec::default();
+    let mut pos = 0;
+
+    while pos + 1 < tokens.len() && tokens[pos + 1].0 == ":" {
+        let key = tokens[pos].0;
+        let start = pos + 2;
+        let mut end = start;
+        let mut depth = 0usize;
+
+        while end < tokens.len() {
+            match tokens[end].0 {
+                "{" | "[" | "(" | "<" => depth += 1,
+                "}" | "]" | ")" | ">" => depth = depth.saturating_sub(1),
+                "," if depth == 0 => break,
+                _ => {}
+            }
+            end += 1;
+        }
+
+        fields.push((key, &tokens[start..end]));
+        pos = end + 1;
+    }
+
+    fields
+}
+
+/// Get string literals from tokens
+fn strings<'t>(tokens: &'t [Tok<'t>]) -> impl Iterator<Item = String> + 't {
+    tokens.iter().filter_map(|(_, string)| string.clone())
+}
+
+/// Parse Rust integer literal
+fn parse_int(tokens: &[Tok<'_>]) -> Option<u16> {
+    let literal = tokens
+        .iter()
+        .map(|(slice, _)| *slice)
+        .collect::<String>()
+        .replace('_', "");
+
+    if let Some(digits) = literal.strip_prefix("0o") {
+        u16::from_str_radix(digits, 8).ok()
+    } else if let Some(digits) = literal.strip_prefix("0x") {
+        u16::from_str_radix(digits, 16).ok()
+    } else if let Some(digits) = literal.strip_prefix("0b") {
+        u16::from_str_radix(digits, 2).ok()
+    } else {
+        literal.parse().ok()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn module_macro() {
+        let module = ModuleData::from_rust_source(
+            r#"
+//! Rust minimal sample.
+
+use kernel::prelude::*;
+
+module! {
+    type: RustMinimal,
+    name: "rust_minimal",
+    authors: ["Rust for Linux Contributors", "Jane Doe"],
+    description: "Rust minimal sample",
+    license: "GPL",
+    alias: ["platform:rust-minimal"],
+    params: {
+        count: u32 {
+            default: 1,
+            permissions: 0o644,
+            description: "Number of items",
+        },
+    },
+}
+
+struct RustMinimal {
+    numbers: KVec<i32>,
+}
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(module.name, "rust_minimal");
+        assert_eq!(module.authors, ["Rust for Linux Contributors", "Jane Doe"]);
+        assert_eq!(module.description, "Rust minimal sample");
+        assert_eq!(module.license, "GPL");
+        assert_eq!(module.aliases, ["platform:rust-minimal"]);
+
+        let param = &module.params["count"];
+        assert_eq!(param.type_, "u32");
+        assert_eq!(param.perm, 0o644);
+        assert_eq!(param.description, "Number of items");
+    }
+
+    #[test]
+    fn module_macro_missing() {
+        // macros which names ends with `module` is not module declarations
+        assert!(ModuleData::from_rust_source("my_module! { name: \"foo\" }").is_none());
+        assert!(ModuleData::from_rust_source("kernel::module! {}").is_none());
+    }
+}
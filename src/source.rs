@@ -1,7 +1,10 @@
-use crate::{FileMgr, ModuleData, Path, Result, SourceData};
+use crate::{
+    DeviceTableData, ExportData, FileMgr, HeaderData, ModuleData, Path, PathBuf, Result,
+    SourceData, SourceLang,
+};
 
 use clex::{Lexer, Token};
-use std::collections::BTreeSet as Set;
+use std::collections::{BTreeMap as Map, BTreeSet as Set};
 use tokio::io::AsyncReadExt;
 
 impl SourceData {
@@ -12,9 +15,35 @@ impl SourceData {
 
         file.read_to_string(&mut src).await?;
 
-        let lexer = Lexer::from(src.as_ref());
+        Self::parse(filemgr, path, &src).await
+    }
+
+    /// Parse already read contents of source file
+    pub(crate) async fn parse(filemgr: &FileMgr, path: &Path, src: &str) -> Result<Self> {
+        let lang = SourceLang::from_path(path).unwrap_or_default();
+
+        match lang {
+            SourceLang::C => {}
+            SourceLang::Asm => {
+                return Ok(Self {
+                    lang,
+                    includes: resolve_includes(filemgr, path, src).await?,
+                    ..Default::default()
+                })
+            }
+            SourceLang::Rust => {
+                return Ok(Self {
+                    lang,
+                    module: ModuleData::from_rust_source(src),
+                    ..Default::default()
+                })
+            }
+        }
+
+        let lexer = Lexer::from(src);
 
         let mut compat_strs = Set::default();
+        let mut exports = Map::default();
         let mut module = ModuleData::default();
 
         #[derive(Clone)]
@@ -105,6 +134,39 @@ impl SourceData {
                 name: String,
                 string: String,
             },
+
+            // exported symbol
+            Export {
+                gpl: bool,
+                ns: bool,
+            },
+            ExportLParen {
+                gpl: bool,
+                ns: bool,
+            },
+            ExportLParenName {
+                gpl: bool,
+                ns: bool,
+                name: String,
+            },
+            // namespaced only
+            ExportLParenNameComma {
+                gpl: bool,
+                name: String,
+            },
+            // namespaced only
+            ExportLParenNameCommaNs {
+                gpl: bool,
+                name: String,
+                namespace: String,
+            },
+
+            // imported namespace
+            ModuleImportNs,
+            ModuleImportNsLParen,
+            ModuleImportNsLParenNs {
+                namespace: String,
+            },
         }
 
         #[derive(Clone, Copy)]
@@ -157,8 +219,25 @@ impl SourceData {
                                     state = State::ModuleParDesc;
                                     continue;
                                 }
+                                "IMPORT_NS" => {
+                                    state = State::ModuleImportNs;
+                                    continue;
+                                }
                                 _ => {}
                             }
+                        } else if let Some((gpl, ns)) = lexeme
+                            .slice
+                            .strip_prefix("EXPORT_SYMBOL")
+                            .and_then(|suffix| match suffix {
+                                "" => Some((false, false)),
+                                "_GPL" => Some((true, false)),
+                                "_NS" => Some((false, true)),
+                                "_NS_GPL" => Some((true, true)),
+                                _ => None,
+                            })
+                        {
+                            state = State::Export { gpl, ns };
+                            continue;
                         } else if lexeme.slice.starts_with("module_param") {
                             match &lexeme.slice[12..] {
                                 "" => {
@@ -294,6 +373,95 @@ impl SourceData {
                     }
                 }
 
+                // exported symbol
+                State::Export { gpl, ns } => {
+                    // EXPORT_SYMBOL(
+                    if lexeme.token == Token::Symbol && lexeme.slice == "(" {
+                        state = State::ExportLParen { gpl, ns };
+                        continue;
+                    }
+                }
+                State::ExportLParen { gpl, ns } => {
+                    // EXPORT_SYMBOL(name
+                    if lexeme.token == Token::Identifier {
+                        state = State::ExportLParenName {
+                            gpl,
+                            ns,
+                            name: lexeme.slice.into(),
+                        };
+                        continue;
+                    }
+                }
+                State::ExportLParenName { gpl, ns, name } => {
+                    if lexeme.token == Token::Symbol {
+                        if ns && lexeme.slice == "," {
+                            // EXPORT_SYMBOL_NS(name,
+                            state = State::ExportLParenNameComma { gpl, name };
+                            continue;
+                        } else if !ns && lexeme.slice == ")" {
+                            // EXPORT_SYMBOL(name)
+                            exports.insert(
+                                name,
+                                ExportData {
+                                    gpl,
+                                    ..Default::default()
+                                },
+                            );
+                        }
+                    }
+                }
+                State::ExportLParenNameComma { gpl, name } => {
+                    // EXPORT_SYMBOL_NS(name, NS or EXPORT_SYMBOL_NS(name, "NS"
+                    let namespace = match lexeme.token {
+                        Token::Identifier => Some(lexeme.slice.into()),
+                        Token::String => lexeme.string(),
+                        _ => None,
+                    };
+                    if let Some(namespace) = namespace {
+                        state = State::ExportLParenNameCommaNs {
+                            gpl,
+                            name,
+                            namespace,
+                        };
+                        continue;
+                    }
+                }
+                State::ExportLParenNameCommaNs {
+                    gpl,
+                    name,
+                    namespace,
+                } => {
+                    // EXPORT_SYMBOL_NS(name, NS)
+                    if lexeme.token == Token::Symbol && lexeme.slice == ")" {
+                        exports.insert(name, ExportData { gpl, namespace });
+                    }
+                }
+
+                // imported namespace
+                State::ModuleImportNs => {
+                    if lexeme.token == Token::Symbol && lexeme.slice == "(" {
+                        state = State::ModuleImportNsLParen;
+                        continue;
+                    }
+                }
+                State::ModuleImportNsLParen => {
+                    // MODULE_IMPORT_NS(NS or MODULE_IMPORT_NS("NS"
+                    let namespace = match lexeme.token {
+                        Token::Identifier => Some(lexeme.slice.into()),
+                        Token::String => lexeme.string(),
+                        _ => None,
+                    };
+                    if let Some(namespace) = namespace {
+                        state = State::ModuleImportNsLParenNs { namespace };
+                        continue;
+                    }
+                }
+                State::ModuleImportNsLParenNs { namespace } => {
+                    if lexeme.token == Token::Symbol && lexeme.slice == ")" {
+                        module.imports.insert(namespace);
+                    }
+                }
+
                 // module param
                 State::ModulePar { usafe, named } => {
                     if lexeme.token == Token::Symbol && lexeme.slice == "(" {
@@ -422,8 +590,7 @@ impl SourceData {
                             };
                             continue;
                         } else if lexeme.slice == ")" {
-                            let mut par =
-                                module.params.entry(name).or_insert_with(Default::default);
+                            let par = module.params.entry(name).or_insert_with(Default::default);
                             par.type_ = type_;
                             par.perm = perm;
                         }
@@ -455,6 +622,9 @@ impl SourceData {
 
         Ok(Self {
             compat_strs,
+            exports,
+            device_tables: DeviceTableData::from_source(src),
+            includes: resolve_includes(filemgr, path, src).await?,
             module: if module.is_empty() {
                 None
             } else {
@@ -465,6 +635,131 @@ impl SourceData {
     }
 }
 
+impl HeaderData {
+    pub async fn from_header(filemgr: &FileMgr, path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let mut file = filemgr.open(&path).await?;
+        let mut src = String::new();
+
+        file.read_to_string(&mut src).await?;
+
+        Self::parse(filemgr, path, &src).await
+    }
+
+    /// Parse already read contents of header file
+    pub(crate) async fn parse(filemgr: &FileMgr, path: &Path, src: &str) -> Result<Self> {
+        Ok(Self {
+            includes: resolve_includes(filemgr, path, src).await?,
+            ..Default::default()
+        })
+    }
+}
+
+/// Extract `#include` directives from source
+fn parse_includes(src: &str) -> impl Iterator<Item = (&str, bool)> {
+    src.lines().filter_map(|line| {
+        let line = line
+            .trim_start()
+            .strip_prefix('#')?
+            .trim_start()
+            .strip_prefix("include")?
+            .trim_start();
+
+        if let Some(name) = line.strip_prefix('<') {
+            name.split_once('>').map(|(name, _)| (name.trim(), true))
+        } else if let Some(name) = line.strip_prefix('"') {
+            name.split_once('"').map(|(name, _)| (name.trim(), false))
+        } else {
+            None
+        }
+    })
+}
+
+/// Resolve included files against source tree
+///
+/// Search order follows kernel build: directory of including file (for `"..."` includes),
+/// `arch/<arch>/include`, `include`, `arch/<arch>/include/uapi`, `include/uapi` and
+/// `include/asm-generic` for `asm/*.h`. Architecture is known only for files under
+/// `arch/<arch>`, so other files resolves `asm/*.h` to generic headers.
+/// Files which cannot be found (like generated or compiler headers) is skipped.
+async fn resolve_includes(filemgr: &FileMgr, path: &Path, src: &str) -> Result<Set<PathBuf>> {
+    let include_dirs = if let Some(arch) = arch_of(path) {
+        let arch_dir = Path::new("arch").join(arch);
+        vec![
+            arch_dir.join("include"),
+            "include".into(),
+            arch_dir.join("include/uapi"),
+            "include/uapi".into(),
+        ]
+    } else {
+        vec!["include".into(), "include/uapi".into()]
+    };
+
+    let mut includes = Set::default();
+
+    'includes: for (name, system) in parse_includes(src) {
+        if !system {
+            if let Some(dir) = path.parent() {
+                let include_path = normalize_path(dir.join(name));
+                if filemgr.file_exists(&include_path).await? {
+                    includes.insert(include_path);
+                    continue;
+                }
+            }
+        }
+
+        for dir in &include_dirs {
+            let include_path = normalize_path(dir.join(name));
+            if filemgr.file_exists(&include_path).await? {
+                includes.insert(include_path);
+                continue 'includes;
+            }
+        }
+
+        if let Some(name) = name.strip_prefix("asm/") {
+            let include_path = Path::new("include/asm-generic").join(name);
+            if filemgr.file_exists(&include_path).await? {
+                includes.insert(include_path);
+                continue;
+            }
+        }
+
+        log::trace!("Unresolved include {:?} in {:?}", name, path);
+    }
+
+    Ok(includes)
+}
+
+/// Get architecture of file under `arch` directory (like `arm64` for `arch/arm64/kernel/smp.c`)
+fn arch_of(path: &Path) -> Option<&str> {
+    let mut components = path.iter();
+
+    if components.next()? != "arch" {
+        return None;
+    }
+
+    components.next()?.to_str()
+}
+
+/// Resolve `.` and `..` components of relative path
+pub(crate) fn normalize_path(path: impl AsRef<Path>) -> PathBuf {
+    use std::path::Component;
+
+    let mut result = PathBuf::default();
+
+    for component in path.as_ref().components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                result.pop();
+            }
+            component => result.push(component),
+        }
+    }
+
+    result
+}
+
 fn mode_from_id(id: &str) -> u16 {
     if let Some(sfx) = id.strip_prefix("S_I") {
         match sfx {
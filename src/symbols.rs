@@ -0,0 +1,318 @@
+use crate::{FileMgr, KmodData, MetaData, ModuleData, Path, PathBuf, Result};
+use clex::{Lexer, Token};
+use std::collections::{BTreeMap as Map, BTreeSet as Set};
+use tokio::io::AsyncReadExt;
+
+/// Licenses which considered GPL-compatible by kernel (see `license_is_gpl_compatible()`)
+const GPL_COMPATIBLE_LICENSES: &[&str] = &[
+    "GPL",
+    "GPL v2",
+    "GPL and additional rights",
+    "Dual BSD/GPL",
+    "Dual MIT/GPL",
+    "Dual MPL/GPL",
+];
+
+impl MetaData {
+    /// Index references to exported symbols
+    ///
+    /// Sources are scanned again after indexing because exported symbols should be known
+    /// to filter out all other identifiers. Unchanged sources keeps previous references.
+    pub(crate) async fn index_symbol_refs(
+        &mut self,
+        filemgr: &FileMgr,
+        unchanged: &Set<PathBuf>,
+    ) -> Result<()> {
+        let exports = self.exported_symbols();
+
+        self.scan_symbol_refs(filemgr, &exports, unchanged).await
+    }
+
+    /// Collect references to given exported symbols from sources
+    pub(crate) async fn scan_symbol_refs(
+        &mut self,
+        filemgr: &FileMgr,
+        exports: &Set<String>,
+        unchanged: &Set<PathBuf>,
+    ) -> Result<()> {
+        for (path, source_data) in &mut self.sources {
+            if !source_data.lang.is_c() || unchanged.contains(path) {
+                continue;
+            }
+
+            let mut file = filemgr.open(path).await?;
+            let mut src = String::new();
+
+            file.read_to_string(&mut src).await?;
+
+            source_data.symbol_refs = Lexer::from(src.as_ref())
+                .filter(|lexeme| {
+                    lexeme.token == Token::Identifier
+                        && exports.contains(lexeme.slice)
+                        && !source_data.exports.contains_key(lexeme.slice)
+                })
+                .map(|lexeme| lexeme.slice.into())
+                .collect();
+        }
+
+        Ok(())
+    }
+
+    /// Get names of all exported symbols
+    pub fn exported_symbols(&self) -> Set<String> {
+        self.sources
+            .values()
+            .flat_map(|source_data| source_data.exports.keys().cloned())
+            .collect()
+    }
+
+    /// Get sources which exports symbols used by source
+    ///
+    /// Returns exporting sources with used symbols.
+    pub fn source_deps(&self, source: impl AsRef<Path>) -> Map<&PathBuf, Set<&String>> {
+        let mut deps = Map::<_, Set<_>>::default();
+
+        if let Some(source_data) = self.source(source) {
+            for symbol in &source_data.symbol_refs {
+                if let Some(symbol_data) = self.symbol(symbol) {
+                    for exporter in &symbol_data.exported_by {
+                        deps.entry(exporter).or_default().insert(symbol);
+                    }
+                }
+            }
+        }
+
+        deps
+    }
+
+    /// Get modules which exports symbols used by source
+    ///
+    /// Returns names of modules (`vmlinux` for built-in code) with used symbols and
+    /// exporting sources. Symbols exported by same module as source are skipped.
+    pub fn module_deps(&self, source: impl AsRef<Path>) -> Map<String, Set<(&String, &PathBuf)>> {
+        let source = source.as_ref();
+        let kmods = self
+            .kmods_of_source(source)
+            .map(|(kmod, _)| kmod)
+            .collect::<Set<_>>();
+        let mut deps = Map::<_, Set<_>>::default();
+
+        for (exporter, symbols) in self.source_deps(source) {
+            let exporter_kmods = self
+                .kmods_of_source(exporter)
+                .map(|(kmod, _)| kmod)
+                .collect::<Vec<_>>();
+
+            if exporter == source || exporter_kmods.iter().any(|kmod| kmods.contains(kmod)) {
+                continue;
+            }
+
+            let names = if exporter_kmods.is_empty() {
+                vec![KmodData::VMLINUX.to_string()]
+            } else {
+                exporter_kmods
+                    .into_iter()
+                    .map(|kmod| KmodData::name_of(kmod.to_string_lossy()))
+                    .collect()
+            };
+
+            for name in names {
+                deps.entry(name)
+                    .or_default()
+                    .extend(symbols.iter().map(|symbol| (*symbol, exporter)));
+            }
+        }
+
+        deps
+    }
+
+    /// Get configuration options which source depends on through used symbols
+    pub fn source_deps_config_opts(&self, source: impl AsRef<Path>) -> Set<&String> {
+        self.source_deps(source)
+            .into_keys()
+            .filter_map(|exporter| self.source(exporter))
+            .flat_map(|source_data| &source_data.config_opts)
+            .collect()
+    }
+
+    /// Get GPL-only symbols used by source of module with non-GPL-compatible license
+    ///
+    /// Returns used symbols with exporting sources.
+    pub fn gpl_violations(&self, source: impl AsRef<Path>) -> Vec<(&String, &PathBuf)> {
+        let source = source.as_ref();
+        let source_data = match self.source(source) {
+            Some(source_data) => source_data,
+            None => return Vec::default(),
+        };
+
+        // license may be declared in another source of same module,
+        // source linked into several modules violates if any of them is non-GPL
+        let modules = if self.kmods_of_source(source).next().is_some() {
+            self.kmods_of_source(source)
+                .map(|(kmod, _)| self.kmod_module(kmod))
+                .collect()
+        } else {
+            self.source_module(source).into_iter().collect::<Vec<_>>()
+        };
+
+        if !modules
+            .iter()
+            .any(|module| !module.license.is_empty() && !module.is_gpl_compatible())
+        {
+            return Vec::default();
+        }
+
+        source_data
+            .symbol_refs
+            .iter()
+            .flat_map(|symbol| {
+                self.symbol_exports(symbol)
+                    .filter(|(_, _, export_data)| export_data.gpl)
+                    .map(move |(exporter, _, _)| (symbol, exporter))
+            })
+            .collect()
+    }
+}
+
+impl ModuleData {
+    /// Check that module license is GPL-compatible
+    pub fn is_gpl_compatible(&self) -> bool {
+        GPL_COMPATIBLE_LICENSES.contains(&self.license.as_str())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{ExportData, MemTree};
+
+    #[test]
+    fn module_deps() {
+        let mut metadata = MetaData::default();
+
+        for (source, exports, refs) in [
+            ("iommu.c", &["iommu_register"][..], &[][..]),
+            ("smmu.c", &["smmu_impl"], &["iommu_register", "smmu_impl2"]),
+            ("smmu-impl.c", &["smmu_impl2"], &["smmu_impl", "qcom_scm"]),
+            ("scm.c", &["qcom_scm"], &[]),
+        ] {
+            let source_data = metadata.source_mut(source);
+            for symbol in exports {
+                source_data
+                    .exports
+                    .insert(symbol.to_string(), ExportData::default());
+            }
+            source_data
+                .symbol_refs
+                .extend(refs.iter().map(|symbol| symbol.to_string()));
+        }
+        for source in ["smmu.c", "smmu-impl.c"] {
+            metadata.kmod_mut("arm-smmu.ko").add_source(source);
+        }
+        metadata.kmod_mut("qcom-scm.ko").add_source("scm.c");
+        metadata.sync_with_sources();
+
+        let deps = |source| {
+            metadata
+                .module_deps(source)
+                .into_iter()
+                .map(|(module, symbols)| {
+                    let symbols = symbols
+                        .into_iter()
+                        .map(|(symbol, _)| symbol.as_str())
+                        .collect::<Vec<_>>();
+                    (module, symbols)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(deps("smmu.c"), [("vmlinux".into(), vec!["iommu_register"])]);
+        assert_eq!(deps("smmu-impl.c"), [("qcom_scm".into(), vec!["qcom_scm"])]);
+    }
+
+    #[test]
+    fn shared_source_violations() {
+        let mut metadata = MetaData::default();
+
+        metadata.source_mut("core.c").exports.insert(
+            "gpl_only".into(),
+            ExportData {
+                gpl: true,
+                ..Default::default()
+            },
+        );
+        metadata
+            .source_mut("helper.c")
+            .symbol_refs
+            .insert("gpl_only".into());
+        for (source, license) in [("gpl.c", "GPL"), ("proprietary.c", "Proprietary")] {
+            metadata.source_mut(source).module = Some(ModuleData {
+                license: license.into(),
+                ..Default::default()
+            });
+        }
+        for (kmod, source) in [("gpl.ko", "gpl.c"), ("proprietary.ko", "proprietary.c")] {
+            metadata.kmod_mut(kmod).add_source(source);
+            metadata.kmod_mut(kmod).add_source("helper.c");
+        }
+        metadata.sync_with_sources();
+
+        let violations = metadata.gpl_violations("helper.c");
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].0, "gpl_only");
+
+        let module = metadata.source_module("helper.c").unwrap();
+        assert_eq!(module.license, "GPL");
+    }
+
+    #[tokio::test]
+    async fn namespaced_exports() {
+        let tree = MemTree::new("linux");
+
+        tree.add_file("Makefile", "obj-y += core.o\nobj-m += foo.o bar.o\n");
+        tree.add_file(
+            "core.c",
+            "int foo_core_register(void) { return 0; }\nEXPORT_SYMBOL_NS_GPL(foo_core_register, FOO_CORE);\n",
+        );
+        for (source, license) in [("foo.c", "GPL"), ("bar.c", "Proprietary")] {
+            tree.add_file(
+                source,
+                format!(
+                    "static int probe(void) {{ return foo_core_register(); }}\nMODULE_IMPORT_NS(FOO_CORE);\nMODULE_LICENSE(\"{}\");\n",
+                    license
+                ),
+            );
+        }
+
+        let filemgr = FileMgr::from_tree(tree);
+        let mut metadata = MetaData::from_kbuild_dir(&filemgr, Path::new(""), None)
+            .await
+            .unwrap();
+        metadata
+            .index_symbol_refs(&filemgr, &Set::default())
+            .await
+            .unwrap();
+        metadata.sync_with_sources();
+
+        let (exporter, _, export_data) =
+            metadata.symbol_exports("foo_core_register").next().unwrap();
+        assert_eq!(exporter, Path::new("core.c"));
+        assert_eq!(export_data.namespace, "FOO_CORE");
+        assert!(export_data.gpl);
+
+        let foo_c = metadata.source("foo.c").unwrap();
+        assert!(foo_c.symbol_refs.contains("foo_core_register"));
+        assert!(foo_c.module.as_ref().unwrap().imports.contains("FOO_CORE"));
+        assert_eq!(
+            metadata
+                .module_deps("foo.c")
+                .into_keys()
+                .collect::<Vec<_>>(),
+            ["vmlinux"]
+        );
+
+        // GPL-only symbol used by non-GPL module
+        assert!(metadata.gpl_violations("foo.c").is_empty());
+        assert_eq!(metadata.gpl_violations("bar.c").len(), 1);
+    }
+}
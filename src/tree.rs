@@ -0,0 +1,355 @@
+use crate::{filemgr, File, FileStamp, Path, PathBuf, Result};
+
+use std::{
+    collections::{BTreeMap as Map, BTreeSet as Set},
+    sync::{Arc, RwLock},
+    time::SystemTime,
+};
+use tokio::sync::Semaphore;
+
+/// Tree of source files
+///
+/// All paths is relative to the root of tree.
+#[async_trait::async_trait]
+pub trait SourceTree: core::fmt::Debug + Send + Sync {
+    /// Get base path of tree (used for messages)
+    fn base_path(&self) -> &PathBuf;
+
+    /// Get hash of tree contents when it is known (like git tree hash)
+    fn tree_id(&self) -> Option<String> {
+        None
+    }
+
+    /// Check directory existing
+    async fn dir_exists(&self, path: &Path) -> Result<bool>;
+
+    /// Check file existing
+    async fn file_exists(&self, path: &Path) -> Result<bool>;
+
+    /// List directory entries
+    async fn list_dir(&self, path: &Path) -> Result<Vec<PathBuf>>;
+
+    /// Get modification time and size of file
+    ///
+    /// Modification time may be any value which changes when file changes.
+    async fn file_meta(&self, path: &Path) -> Result<(u64, u64)>;
+
+    /// Open file for reading
+    async fn open(&self, path: &Path) -> Result<File>;
+
+    /// Create file for writing
+    async fn create(&self, path: &Path) -> Result<File> {
+        anyhow::bail!("Unable to create file {} in read-only tree", path.display())
+    }
+}
+
+/// Tree of files in filesystem directory
+#[derive(Debug)]
+pub struct DirTree {
+    /// Base directory
+    dir: PathBuf,
+
+    /// Open files semaphore
+    sem: Arc<Semaphore>,
+}
+
+impl DirTree {
+    /// Create tree using base directory
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self> {
+        let (soft, hard) = rlimit::Resource::NOFILE.get()?;
+        let max_open_files = soft.min(hard) - 10;
+
+        log::debug!("Max open files: {}", max_open_files);
+
+        Ok(Self {
+            dir: dir.into(),
+            sem: Arc::new(Semaphore::new(max_open_files as _)),
+        })
+    }
+
+    /// Get full path to files in base directory using relative path
+    pub fn full_path(&self, path: impl AsRef<Path>) -> Result<PathBuf> {
+        let path = path.as_ref();
+        if path.is_absolute() {
+            anyhow::bail!("Path should be relative: {}", path.display());
+        }
+        Ok(self.dir.join(path))
+    }
+}
+
+#[async_trait::async_trait]
+impl SourceTree for DirTree {
+    fn base_path(&self) -> &PathBuf {
+        &self.dir
+    }
+
+    async fn dir_exists(&self, path: &Path) -> Result<bool> {
+        Ok(filemgr::dir_exists(self.full_path(path)?).await)
+    }
+
+    async fn file_exists(&self, path: &Path) -> Result<bool> {
+        Ok(filemgr::file_exists(self.full_path(path)?).await)
+    }
+
+    async fn list_dir(&self, path: &Path) -> Result<Vec<PathBuf>> {
+        let mut dir = tokio::fs::read_dir(self.full_path(path)?).await?;
+        let mut entries = Vec::default();
+
+        while let Some(entry) = dir.next_entry().await? {
+            entries.push(path.join(entry.file_name()));
+        }
+
+        Ok(entries)
+    }
+
+    async fn file_meta(&self, path: &Path) -> Result<(u64, u64)> {
+        let meta = tokio::fs::metadata(self.full_path(path)?).await?;
+        let mtime = meta
+            .modified()?
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|duration| duration.as_nanos() as u64)
+            .unwrap_or_default();
+
+        Ok((mtime, meta.len()))
+    }
+
+    async fn open(&self, path: &Path) -> Result<File> {
+        let perm = Semaphore::acquire_owned(self.sem.clone()).await?;
+        let file = tokio::fs::File::open(self.full_path(path)?).await?;
+
+        Ok(File::from_fs(file, perm))
+    }
+
+    async fn create(&self, path: &Path) -> Result<File> {
+        let perm = Semaphore::acquire_owned(self.sem.clone()).await?;
+        let file = tokio::fs::File::create(self.full_path(path)?).await?;
+
+        Ok(File::from_fs(file, perm))
+    }
+}
+
+/// Files contents with hash of contents by path
+type MemFiles = Map<PathBuf, (u64, Arc<[u8]>)>;
+
+/// Entries names by directory path
+type MemDirs = Map<PathBuf, Set<PathBuf>>;
+
+/// Tree of files in memory
+///
+/// Directories is implied by paths of files.
+#[derive(Debug, Default)]
+pub struct MemTree {
+    /// Base path (used for messages)
+    base: PathBuf,
+
+    /// Hash of tree contents when it is known
+    id: Option<String>,
+
+    /// Files and directories
+    data: RwLock<(MemFiles, MemDirs)>,
+}
+
+impl MemTree {
+    /// Create empty tree
+    pub fn new(base: impl Into<PathBuf>) -> Self {
+        Self {
+            base: base.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Set hash of tree contents (used as index cache key)
+    pub fn with_tree_id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Add file or replace contents of existing file
+    pub fn add_file(&self, path: impl Into<PathBuf>, data: impl Into<Vec<u8>>) {
+        let path = path.into();
+        let data = data.into();
+        // hash is used as modification time to be comparable between trees
+        let hash = FileStamp::hash_of(&data);
+        let (files, dirs) = &mut *self.data.write().unwrap();
+
+        let mut entry = path.as_path();
+        while let Some(dir) = entry.parent() {
+            if !dirs.entry(dir.into()).or_default().insert(entry.into()) {
+                break;
+            }
+            entry = dir;
+        }
+
+        files.insert(path, (hash, data.into()));
+    }
+
+    /// Remove file
+    pub fn remove_file(&self, path: impl AsRef<Path>) -> bool {
+        let path = path.as_ref();
+        let (files, dirs) = &mut *self.data.write().unwrap();
+
+        if files.remove(path).is_none() {
+            return false;
+        }
+
+        // remove directories which becomes empty
+        let mut entry = path;
+        while let Some(dir) = entry.parent() {
+            let entries = dirs.get_mut(dir).unwrap();
+            entries.remove(entry);
+            if !entries.is_empty() || dir.as_os_str().is_empty() {
+                break;
+            }
+            dirs.remove(dir);
+            entry = dir;
+        }
+
+        true
+    }
+
+    /// Get file hash and contents
+    fn file(&self, path: &Path) -> Result<(u64, Arc<[u8]>)> {
+        self.data
+            .read()
+            .unwrap()
+            .0
+            .get(root_relative(path))
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("File not found: {}", path.display()))
+    }
+}
+
+#[async_trait::async_trait]
+impl SourceTree for MemTree {
+    fn base_path(&self) -> &PathBuf {
+        &self.base
+    }
+
+    fn tree_id(&self) -> Option<String> {
+        self.id.clone()
+    }
+
+    async fn dir_exists(&self, path: &Path) -> Result<bool> {
+        Ok(self
+            .data
+            .read()
+            .unwrap()
+            .1
+            .contains_key(root_relative(path)))
+    }
+
+    async fn file_exists(&self, path: &Path) -> Result<bool> {
+        Ok(self
+            .data
+            .read()
+            .unwrap()
+            .0
+            .contains_key(root_relative(path)))
+    }
+
+    async fn list_dir(&self, path: &Path) -> Result<Vec<PathBuf>> {
+        let data = self.data.read().unwrap();
+        let entries = data
+            .1
+            .get(root_relative(path))
+            .ok_or_else(|| anyhow::anyhow!("Directory not found: {}", path.display()))?;
+
+        Ok(entries
+            .iter()
+            .filter_map(|entry| entry.file_name())
+            .map(|name| path.join(name))
+            .collect())
+    }
+
+    async fn file_meta(&self, path: &Path) -> Result<(u64, u64)> {
+        let (hash, data) = self.file(path)?;
+
+        Ok((hash, data.len() as _))
+    }
+
+    async fn open(&self, path: &Path) -> Result<File> {
+        let (_, data) = self.file(path)?;
+
+        Ok(File::from_data(data))
+    }
+}
+
+/// Treat `.` as root of tree
+fn root_relative(path: &Path) -> &Path {
+    path.strip_prefix(".").unwrap_or(path)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn mem_tree_dirs() {
+        let tree = MemTree::new("linux");
+        tree.add_file("drivers/foo/foo.c", "int foo;\n");
+        tree.add_file("drivers/foo/foo.h", "");
+        tree.add_file("Makefile", "");
+
+        assert!(tree.dir_exists(Path::new("drivers/foo")).await.unwrap());
+        assert!(tree.dir_exists(Path::new(".")).await.unwrap());
+        assert!(tree.file_exists(Path::new("./Makefile")).await.unwrap());
+        assert_eq!(
+            tree.list_dir(Path::new("drivers/foo")).await.unwrap(),
+            [
+                PathBuf::from("drivers/foo/foo.c"),
+                "drivers/foo/foo.h".into()
+            ]
+        );
+        assert_eq!(
+            tree.file_meta(Path::new("drivers/foo/foo.c"))
+                .await
+                .unwrap()
+                .1,
+            9
+        );
+
+        assert!(tree.remove_file("drivers/foo/foo.c"));
+        assert!(!tree.remove_file("drivers/foo/foo.c"));
+        assert!(tree.dir_exists(Path::new("drivers/foo")).await.unwrap());
+
+        // empty directories is removed with last file
+        assert!(tree.remove_file("drivers/foo/foo.h"));
+        assert!(!tree.dir_exists(Path::new("drivers")).await.unwrap());
+        assert_eq!(
+            tree.list_dir(Path::new("")).await.unwrap(),
+            [PathBuf::from("Makefile")]
+        );
+        assert!(tree.open(Path::new("drivers/foo/foo.h")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn mem_tree_stamps() {
+        let (old, new) = (MemTree::new("old"), MemTree::new("new"));
+        old.add_file("foo.c", "int foo;\n");
+        new.add_file("foo.c", "int foo;\n");
+
+        // same contents gives same stamps in different trees
+        let path = Path::new("foo.c");
+        assert_eq!(
+            old.file_meta(path).await.unwrap(),
+            new.file_meta(path).await.unwrap()
+        );
+
+        new.add_file("foo.c", "int bar;\n");
+        assert_ne!(
+            old.file_meta(path).await.unwrap(),
+            new.file_meta(path).await.unwrap()
+        );
+    }
+
+    #[test]
+    fn dir_tree_paths() {
+        let tree = DirTree::new("/usr/src/linux").unwrap();
+
+        assert_eq!(
+            tree.full_path("drivers/foo.c").unwrap(),
+            Path::new("/usr/src/linux/drivers/foo.c")
+        );
+        assert!(tree.full_path("/etc/passwd").is_err());
+    }
+}